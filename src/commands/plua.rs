@@ -1,8 +1,13 @@
+use std::sync::Mutex;
+
 use async_trait::async_trait;
 use pumpkin::{
     command::{
         CommandExecutor, CommandSender,
-        args::{Arg, ConsumedArgs, message::MsgArgConsumer},
+        args::{
+            Arg, ArgumentConsumer, ConsumedArgs, GetClientSideArgParser, RawArgs,
+            message::MsgArgConsumer,
+        },
         dispatcher::CommandError,
         tree::{
             CommandTree,
@@ -11,39 +16,133 @@ use pumpkin::{
     },
     server::Server,
 };
+use pumpkin_protocol::java::client::play::{
+    ArgumentType, CommandSuggestion, StringProto, SuggestionProviders,
+};
 use pumpkin_util::text::{TextComponent, color::NamedColor};
 
 use crate::lua;
+use crate::lua::events::delivery;
+use crate::lua::memory;
 
 const NAMES: [&str; 1] = ["plua"];
 const DESCRIPTION: &str = "Manage Lua plugins for the Pumpkin server";
 
+const REPL_NAMES: [&str; 1] = ["lua"];
+const REPL_DESCRIPTION: &str = "Evaluate Lua in the plugin selected with /plua repl";
+
 const ARG_PLUGIN_NAME: &str = "plugin_name";
+const ARG_CODE: &str = "code";
 
 pub const PERMISSION_NODE: &str = "plua:command.plua";
 
+/// Needed on top of [`PERMISSION_NODE`] to evaluate Lua code.
+pub const EVAL_PERMISSION_NODE: &str = "plua:command.plua.eval";
+
+/// The plugin the console REPL evaluates code in, if a session is open.
+static REPL_PLUGIN: Mutex<Option<String>> = Mutex::new(None);
+
+/// Plugin name arguments: the rest of the line, or one word when more arguments follow.
+const PLUGIN_NAME: PluginNameArgConsumer = PluginNameArgConsumer { greedy: true };
+const PLUGIN_WORD: PluginNameArgConsumer = PluginNameArgConsumer { greedy: false };
+
 pub fn init_command_tree() -> CommandTree {
     CommandTree::new(NAMES, DESCRIPTION)
         .then(literal("list").execute(ListPluginsExecutor {}))
         .then(
             literal("enable")
-                .then(argument(ARG_PLUGIN_NAME, MsgArgConsumer).execute(EnablePluginExecutor {})),
+                .then(argument(ARG_PLUGIN_NAME, PLUGIN_NAME).execute(EnablePluginExecutor {})),
         )
         .then(
             literal("disable")
-                .then(argument(ARG_PLUGIN_NAME, MsgArgConsumer).execute(DisablePluginExecutor {})),
+                .then(argument(ARG_PLUGIN_NAME, PLUGIN_NAME).execute(DisablePluginExecutor {})),
         )
         .then(
             literal("reload")
                 .execute(ReloadAllExecutor {})
-                .then(argument(ARG_PLUGIN_NAME, MsgArgConsumer).execute(ReloadPluginExecutor {})),
+                .then(argument(ARG_PLUGIN_NAME, PLUGIN_NAME).execute(ReloadPluginExecutor {})),
         )
         .then(
             literal("info")
-                .then(argument(ARG_PLUGIN_NAME, MsgArgConsumer).execute(PluginInfoExecutor {})),
+                .then(argument(ARG_PLUGIN_NAME, PLUGIN_NAME).execute(PluginInfoExecutor {})),
+        )
+        .then(literal("stats").execute(StatsExecutor {}))
+        .then(
+            literal("eval").then(
+                argument(ARG_PLUGIN_NAME, PLUGIN_WORD)
+                    .then(argument(ARG_CODE, MsgArgConsumer).execute(EvalExecutor {})),
+            ),
+        )
+        .then(
+            literal("repl")
+                .execute(ReplExecutor {})
+                .then(argument(ARG_PLUGIN_NAME, PLUGIN_NAME).execute(ReplExecutor {})),
         )
 }
 
+/// The `/lua <code>` command used to type into the console REPL.
+pub fn init_repl_command_tree() -> CommandTree {
+    CommandTree::new(REPL_NAMES, REPL_DESCRIPTION)
+        .then(argument(ARG_CODE, MsgArgConsumer).execute(ReplEvalExecutor {}))
+}
+
+/// Consumes a plugin name and completes the names of the discovered Lua plugins.
+struct PluginNameArgConsumer {
+    /// Whether the name is the rest of the command rather than a single word.
+    greedy: bool,
+}
+
+impl GetClientSideArgParser for PluginNameArgConsumer {
+    fn get_client_side_parser(&self) -> ArgumentType {
+        if self.greedy {
+            ArgumentType::String(StringProto::GreedyPhrase)
+        } else {
+            ArgumentType::String(StringProto::SingleWord)
+        }
+    }
+
+    fn get_client_side_suggestion_type_override(&self) -> Option<SuggestionProviders> {
+        Some(SuggestionProviders::AskServer)
+    }
+}
+
+#[async_trait]
+impl ArgumentConsumer for PluginNameArgConsumer {
+    async fn consume<'a>(
+        &'a self,
+        _: &CommandSender,
+        _: &'a Server,
+        args: &mut RawArgs<'a>,
+    ) -> Option<Arg<'a>> {
+        let mut name = args.pop()?.to_string();
+        if self.greedy {
+            while let Some(word) = args.pop() {
+                name.push(' ');
+                name.push_str(word);
+            }
+        }
+
+        Some(Arg::Msg(name))
+    }
+
+    async fn suggest<'a>(
+        &'a self,
+        _: &CommandSender,
+        _: &'a Server,
+        input: &'a str,
+    ) -> Result<Option<Vec<CommandSuggestion>>, CommandError> {
+        let suggestions = lua::get_plugin_list()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| name.starts_with(input))
+            .map(|name| CommandSuggestion::new(name, None))
+            .collect();
+
+        Ok(Some(suggestions))
+    }
+}
+
 struct ListPluginsExecutor {}
 
 #[async_trait]
@@ -54,7 +153,7 @@ impl CommandExecutor for ListPluginsExecutor {
         _: &Server,
         _: &ConsumedArgs<'a>,
     ) -> Result<(), CommandError> {
-        let plugins = lua::get_plugin_list();
+        let plugins = lua::get_plugin_list().await;
 
         if plugins.is_empty() {
             sender
@@ -107,7 +206,7 @@ impl CommandExecutor for EnablePluginExecutor {
             )));
         };
 
-        match lua::enable_plugin(plugin_name) {
+        match lua::enable_plugin(plugin_name).await {
             Ok(true) => {
                 sender
                     .send_message(
@@ -160,7 +259,7 @@ impl CommandExecutor for DisablePluginExecutor {
             )));
         };
 
-        match lua::disable_plugin(plugin_name) {
+        match lua::disable_plugin(plugin_name).await {
             Ok(true) => {
                 sender
                     .send_message(
@@ -207,7 +306,7 @@ impl CommandExecutor for ReloadAllExecutor {
         _: &Server,
         _: &ConsumedArgs<'a>,
     ) -> Result<(), CommandError> {
-        match lua::reload() {
+        match lua::reload().await {
             Ok(_) => {
                 sender
                     .send_message(
@@ -246,7 +345,7 @@ impl CommandExecutor for ReloadPluginExecutor {
             )));
         };
 
-        match lua::reload_plugin(plugin_name) {
+        match lua::reload_plugin(plugin_name).await {
             Ok(true) => {
                 sender
                     .send_message(
@@ -296,12 +395,11 @@ impl CommandExecutor for PluginInfoExecutor {
             )));
         };
 
-        if let Some((name, description, version, author, enabled, file_path)) =
-            lua::get_plugin_info(plugin_name)
-        {
+        if let Some(info) = lua::get_plugin_info(plugin_name).await {
             sender
                 .send_message(
-                    TextComponent::text(format!("=== {} ===", name)).color_named(NamedColor::Gold),
+                    TextComponent::text(format!("=== {} ===", info.name))
+                        .color_named(NamedColor::Gold),
                 )
                 .await;
 
@@ -309,7 +407,7 @@ impl CommandExecutor for PluginInfoExecutor {
                 .send_message(
                     TextComponent::text("Description: ")
                         .color_named(NamedColor::Yellow)
-                        .add_text(description),
+                        .add_text(info.description),
                 )
                 .await;
 
@@ -317,7 +415,7 @@ impl CommandExecutor for PluginInfoExecutor {
                 .send_message(
                     TextComponent::text("Version: ")
                         .color_named(NamedColor::Yellow)
-                        .add_text(version),
+                        .add_text(info.version),
                 )
                 .await;
 
@@ -325,16 +423,16 @@ impl CommandExecutor for PluginInfoExecutor {
                 .send_message(
                     TextComponent::text("Author: ")
                         .color_named(NamedColor::Yellow)
-                        .add_text(author),
+                        .add_text(info.author),
                 )
                 .await;
 
-            let status_color = if enabled {
+            let status_color = if info.enabled {
                 NamedColor::Green
             } else {
                 NamedColor::Red
             };
-            let status_text = if enabled { "Enabled" } else { "Disabled" };
+            let status_text = if info.enabled { "Enabled" } else { "Disabled" };
 
             sender
                 .send_message(
@@ -345,7 +443,7 @@ impl CommandExecutor for PluginInfoExecutor {
                 )
                 .await;
 
-            let path_str = file_path.to_string_lossy().into_owned();
+            let path_str = info.file_path.to_string_lossy().into_owned();
             sender
                 .send_message(
                     TextComponent::text("File: ")
@@ -353,6 +451,36 @@ impl CommandExecutor for PluginInfoExecutor {
                         .add_text(path_str),
                 )
                 .await;
+
+            let overrun_color = if info.budget_overruns > 0 {
+                NamedColor::Red
+            } else {
+                NamedColor::Green
+            };
+            sender
+                .send_message(
+                    TextComponent::text("Budget overruns: ")
+                        .color_named(NamedColor::Yellow)
+                        .add_text(info.budget_overruns.to_string())
+                        .color_named(overrun_color),
+                )
+                .await;
+
+            let memory = match info.memory_limit {
+                Some(limit) => format!(
+                    "{} / {}",
+                    memory::format_bytes(info.memory_used),
+                    memory::format_bytes(limit)
+                ),
+                None => format!("{} (no limit)", memory::format_bytes(info.memory_used)),
+            };
+            sender
+                .send_message(
+                    TextComponent::text("Memory: ")
+                        .color_named(NamedColor::Yellow)
+                        .add_text(memory),
+                )
+                .await;
         } else {
             sender
                 .send_message(
@@ -365,3 +493,190 @@ impl CommandExecutor for PluginInfoExecutor {
         Ok(())
     }
 }
+
+struct StatsExecutor {}
+
+#[async_trait]
+impl CommandExecutor for StatsExecutor {
+    async fn execute<'a>(
+        &self,
+        sender: &mut CommandSender,
+        _: &Server,
+        _: &ConsumedArgs<'a>,
+    ) -> Result<(), CommandError> {
+        let stats = delivery::stats();
+
+        sender
+            .send_message(TextComponent::text("=== Lua Events ===").color_named(NamedColor::Gold))
+            .await;
+
+        let counters = [
+            ("Sent: ", stats.sent),
+            ("Dropped: ", stats.dropped),
+            ("Shed: ", stats.shed),
+            ("Timed out: ", stats.timed_out),
+        ];
+
+        for (label, count) in counters {
+            sender
+                .send_message(
+                    TextComponent::text(label)
+                        .color_named(NamedColor::Yellow)
+                        .add_text(count.to_string()),
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Shows the result of evaluating Lua code, or the error with its traceback.
+async fn send_eval_result(sender: &mut CommandSender, result: anyhow::Result<String>) {
+    let (text, color) = match result {
+        Ok(output) => (output, NamedColor::Green),
+        Err(e) => (e.to_string(), NamedColor::Red),
+    };
+
+    for line in text.lines() {
+        sender
+            .send_message(TextComponent::text(line.to_string()).color_named(color))
+            .await;
+    }
+}
+
+/// Evaluation needs its own permission on top of the one for `/plua`.
+async fn check_eval_permission(sender: &mut CommandSender) -> bool {
+    if sender.has_permission(EVAL_PERMISSION_NODE).await {
+        return true;
+    }
+
+    sender
+        .send_message(
+            TextComponent::text("You do not have permission to evaluate Lua code.")
+                .color_named(NamedColor::Red),
+        )
+        .await;
+    false
+}
+
+struct EvalExecutor {}
+
+#[async_trait]
+impl CommandExecutor for EvalExecutor {
+    async fn execute<'a>(
+        &self,
+        sender: &mut CommandSender,
+        _: &Server,
+        args: &ConsumedArgs<'a>,
+    ) -> Result<(), CommandError> {
+        let Some(Arg::Msg(plugin_name)) = args.get(ARG_PLUGIN_NAME) else {
+            return Err(CommandError::InvalidConsumption(Some(
+                ARG_PLUGIN_NAME.into(),
+            )));
+        };
+
+        let Some(Arg::Msg(code)) = args.get(ARG_CODE) else {
+            return Err(CommandError::InvalidConsumption(Some(ARG_CODE.into())));
+        };
+
+        if !check_eval_permission(sender).await {
+            return Ok(());
+        }
+
+        send_eval_result(sender, lua::eval(plugin_name, code).await).await;
+
+        Ok(())
+    }
+}
+
+struct ReplExecutor {}
+
+#[async_trait]
+impl CommandExecutor for ReplExecutor {
+    async fn execute<'a>(
+        &self,
+        sender: &mut CommandSender,
+        _: &Server,
+        args: &ConsumedArgs<'a>,
+    ) -> Result<(), CommandError> {
+        if !matches!(sender, CommandSender::Console) {
+            sender
+                .send_message(
+                    TextComponent::text("The Lua REPL is only available from the console.")
+                        .color_named(NamedColor::Red),
+                )
+                .await;
+            return Ok(());
+        }
+
+        if !check_eval_permission(sender).await {
+            return Ok(());
+        }
+
+        let message = match args.get(ARG_PLUGIN_NAME) {
+            Some(Arg::Msg(plugin_name)) => {
+                *REPL_PLUGIN.lock().unwrap() = Some(plugin_name.clone());
+                format!(
+                    "Lua REPL opened in plugin '{}'. Type 'lua <code>' to evaluate code and 'plua repl' to close it.",
+                    plugin_name
+                )
+            }
+            _ => match REPL_PLUGIN.lock().unwrap().take() {
+                Some(plugin_name) => format!("Lua REPL in plugin '{}' closed.", plugin_name),
+                None => "Usage: plua repl <plugin_name>".to_string(),
+            },
+        };
+
+        sender
+            .send_message(TextComponent::text(message).color_named(NamedColor::Yellow))
+            .await;
+
+        Ok(())
+    }
+}
+
+struct ReplEvalExecutor {}
+
+#[async_trait]
+impl CommandExecutor for ReplEvalExecutor {
+    async fn execute<'a>(
+        &self,
+        sender: &mut CommandSender,
+        _: &Server,
+        args: &ConsumedArgs<'a>,
+    ) -> Result<(), CommandError> {
+        let Some(Arg::Msg(code)) = args.get(ARG_CODE) else {
+            return Err(CommandError::InvalidConsumption(Some(ARG_CODE.into())));
+        };
+
+        let plugin_name = if matches!(sender, CommandSender::Console) {
+            REPL_PLUGIN.lock().unwrap().clone()
+        } else {
+            None
+        };
+
+        let Some(plugin_name) = plugin_name else {
+            sender
+                .send_message(
+                    TextComponent::text(
+                        "Open the Lua REPL from the console with 'plua repl <plugin_name>' first.",
+                    )
+                    .color_named(NamedColor::Red),
+                )
+                .await;
+            return Ok(());
+        };
+
+        if !check_eval_permission(sender).await {
+            return Ok(());
+        }
+
+        sender
+            .send_message(TextComponent::text(format!("> {}", code)).color_named(NamedColor::Gray))
+            .await;
+        send_eval_result(sender, lua::eval(&plugin_name, code).await).await;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,59 @@
 use anyhow::{Context as AnyhowContext, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct PLuaConfig {
     pub enabled_plugins: Vec<String>,
+    #[serde(default)]
+    pub execution_budget: ExecutionBudgetConfig,
+    #[serde(default)]
+    pub memory_limit: MemoryLimitConfig,
+}
+
+/// How long Lua code may run without pausing before it is aborted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionBudgetConfig {
+    pub default_ms: u64,
+    /// Budgets for single plugins, overriding the default.
+    pub plugins: HashMap<String, u64>,
+    /// Disables a plugin once it has been aborted this many times.
+    pub disable_after: Option<u32>,
+}
+
+impl Default for ExecutionBudgetConfig {
+    fn default() -> Self {
+        Self {
+            default_ms: 100,
+            plugins: HashMap::new(),
+            disable_after: None,
+        }
+    }
+}
+
+/// How much memory Lua plugins may allocate, in MiB.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryLimitConfig {
+    /// The limit of all plugins together, or `None` for no limit.
+    pub total_mb: Option<u64>,
+    /// The limit of each plugin, or `None` for no limit.
+    pub default_mb: Option<u64>,
+    /// Limits for single plugins, overriding the default.
+    pub plugins: HashMap<String, u64>,
+}
+
+impl Default for MemoryLimitConfig {
+    fn default() -> Self {
+        Self {
+            total_mb: Some(512),
+            default_mb: None,
+            plugins: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -41,21 +41,44 @@ impl PLuaPlugin {
         Ok(())
     }
 
-    async fn register_lua_loader(&self, context: &Context) -> Result<(), String> {
-        let lua_loader = match LuaPluginLoader::new() {
-            Ok(loader) => loader,
-            Err(e) => return Err(format!("Failed to create Lua plugin loader: {}", e)),
-        };
+    async fn register_eval_commands(&self, context: &Context) -> Result<(), String> {
+        let permission = Permission::new(
+            crate::commands::plua::EVAL_PERMISSION_NODE,
+            "Allow evaluating Lua code with /plua eval and the console REPL",
+            PermissionDefault::Op(PermissionLvl::Four),
+        );
+        context.register_permission(permission).await?;
+        context
+            .register_command(
+                commands::plua::init_repl_command_tree(),
+                crate::commands::plua::EVAL_PERMISSION_NODE,
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn register_lua_command_permission(&self, context: &Context) -> Result<(), String> {
+        let permission = Permission::new(
+            lua::commands::DEFAULT_PERMISSION_NODE,
+            "Allow running commands registered by Lua plugins",
+            PermissionDefault::Allow,
+        );
+        context.register_permission(permission).await
+    }
 
+    async fn register_lua_loader(&self, context: &Context) -> Result<(), String> {
         lua::events::register_events(context).await?;
 
         let plugin_manager = context.plugin_manager.clone();
-        let loader = Arc::new(lua_loader);
+        let loader = Arc::new(LuaPluginLoader);
         tokio::spawn(async move {
             let plugin_manager = plugin_manager.clone();
             let loader = loader.clone();
             let mut manager = plugin_manager.write().await;
             manager.add_loader(loader).await;
+
+            // Adding the loader loads the Lua plugins in the plugins directory
+            lua::events::server::started();
         });
 
         Ok(())
@@ -70,12 +93,22 @@ async fn on_load(&mut self, context: &Context) -> Result<(), String> {
 
     self.setup_lua(context)?;
     self.register_plua_command(context).await?;
+    self.register_eval_commands(context).await?;
+    self.register_lua_command_permission(context).await?;
 
     self.register_lua_loader(context).await?;
 
     Ok(())
 }
 
+#[plugin_method]
+async fn on_unload(&mut self, _context: &Context) -> Result<(), String> {
+    lua::events::server::shutdown().await;
+    lua::shutdown_lua_manager().await;
+
+    Ok(())
+}
+
 impl Default for PLuaPlugin {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use mlua::{Lua, VmState};
+
+use crate::config::ExecutionBudgetConfig;
+use crate::lua;
+use crate::lua::memory;
+use crate::lua::runtime::active_plugin;
+use crate::lua::worker::LuaCommand;
+
+/// How long a call has been running since it last resumed.
+struct Slice {
+    start: Instant,
+    /// Set once the call's code was aborted, so it keeps failing until it
+    /// stops running and `pcall` cannot swallow the abort.
+    aborting: bool,
+}
+
+impl Slice {
+    fn new(now: Instant) -> Self {
+        Self {
+            start: now,
+            aborting: false,
+        }
+    }
+}
+
+/// Execution budgets and overruns of a Lua state, stored as app data.
+struct Budgets {
+    default: Duration,
+    plugins: HashMap<String, Duration>,
+    disable_after: Option<u32>,
+    overruns: HashMap<String, u32>,
+    /// The slice of each call, keyed by the call's coroutine.
+    slices: HashMap<usize, Slice>,
+    /// The calls being resumed, innermost last. Code run from a call, also in
+    /// coroutines the plugin created itself, counts against the innermost.
+    running: Vec<usize>,
+}
+
+impl Budgets {
+    fn budget(&self, plugin: Option<&str>) -> Duration {
+        plugin
+            .and_then(|name| self.plugins.get(name))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Counts an overrun, returning whether the plugin should now be disabled.
+    fn record_overrun(&mut self, plugin: &str) -> bool {
+        let overruns = self.overruns.entry(plugin.to_string()).or_default();
+        *overruns += 1;
+
+        self.disable_after.is_some_and(|limit| *overruns == limit)
+    }
+}
+
+/// Aborts Lua code that runs longer than its plugin's budget without pausing.
+/// A call into a plugin gets a fresh budget whenever it is resumed, when it
+/// starts and after every wait, so only code that runs too long in one go is
+/// aborted. The interrupt also keeps the memory accounting of plugins up to
+/// date.
+pub fn install(lua: &Lua, config: &ExecutionBudgetConfig) {
+    lua.set_app_data(Budgets {
+        default: Duration::from_millis(config.default_ms),
+        plugins: config
+            .plugins
+            .iter()
+            .map(|(name, ms)| (name.clone(), Duration::from_millis(*ms)))
+            .collect(),
+        disable_after: config.disable_after,
+        overruns: HashMap::new(),
+        slices: HashMap::new(),
+        running: Vec::new(),
+    });
+
+    lua.set_interrupt(|lua_ctx| {
+        let plugin = active_plugin(lua_ctx);
+        let now = Instant::now();
+
+        memory::account(lua_ctx, plugin.as_deref())?;
+
+        let Some(mut budgets) = lua_ctx.app_data_mut::<Budgets>() else {
+            return Ok(VmState::Continue);
+        };
+
+        let budget = budgets.budget(plugin.as_deref());
+
+        let call = budgets.running.last().copied();
+        let Some(slice) = call.and_then(|call| budgets.slices.get_mut(&call)) else {
+            return Ok(VmState::Continue);
+        };
+
+        let name = plugin.as_deref().unwrap_or("unknown");
+        if slice.aborting {
+            return Err(mlua::Error::RuntimeError(format!(
+                "Plugin {} is being aborted for exceeding its execution budget",
+                name
+            )));
+        }
+
+        if now.duration_since(slice.start) <= budget {
+            return Ok(VmState::Continue);
+        }
+
+        slice.aborting = true;
+        let disable = budgets.record_overrun(name);
+        drop(budgets);
+
+        log::error!(
+            "Plugin {} ran for more than {} ms without pausing and was aborted",
+            name,
+            budget.as_millis()
+        );
+
+        if disable && plugin.is_some() {
+            log::warn!("Disabling plugin {} after repeated budget overruns", name);
+            let suspend = lua::manager().and_then(|manager| {
+                manager.send_command(LuaCommand::SuspendPlugin {
+                    name: name.to_string(),
+                })
+            });
+            if let Err(e) = suspend {
+                log::error!("Failed to disable plugin {}: {}", name, e);
+            }
+        }
+
+        Err(mlua::Error::RuntimeError(format!(
+            "Plugin {} exceeded its execution budget of {} ms",
+            name,
+            budget.as_millis()
+        )))
+    });
+}
+
+/// Keeps a call running against its budget until dropped.
+pub(crate) struct Resumed<'a> {
+    lua: &'a Lua,
+    thread: usize,
+}
+
+impl Drop for Resumed<'_> {
+    fn drop(&mut self) {
+        let Some(mut budgets) = self.lua.app_data_mut::<Budgets>() else {
+            return;
+        };
+
+        if let Some(position) = budgets
+            .running
+            .iter()
+            .rposition(|call| *call == self.thread)
+        {
+            budgets.running.remove(position);
+        }
+    }
+}
+
+/// Resumes the call running in the given coroutine with a fresh budget. The
+/// call runs against it until the returned guard is dropped, which happens
+/// once the call finishes or waits.
+pub(crate) fn resume_call(lua: &Lua, thread: usize) -> Resumed<'_> {
+    if let Some(mut budgets) = lua.app_data_mut::<Budgets>() {
+        budgets.slices.insert(thread, Slice::new(Instant::now()));
+        budgets.running.push(thread);
+    }
+
+    Resumed { lua, thread }
+}
+
+/// Forgets the slice of a call that finished.
+pub(crate) fn end_call(lua: &Lua, thread: usize) {
+    if let Some(mut budgets) = lua.app_data_mut::<Budgets>() {
+        budgets.slices.remove(&thread);
+    }
+}
+
+/// How many times a plugin's code was aborted for exceeding its budget.
+pub fn overruns(lua: &Lua, plugin_name: &str) -> u32 {
+    lua.app_data_ref::<Budgets>()
+        .and_then(|budgets| budgets.overruns.get(plugin_name).copied())
+        .unwrap_or(0)
+}
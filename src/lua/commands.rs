@@ -0,0 +1,866 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mlua::{Function, IntoLua, Lua, Table, UserData, UserDataFields, UserDataMethods, Value};
+use pumpkin::{
+    command::{
+        CommandExecutor, CommandSender,
+        args::{
+            Arg, ArgumentConsumer, ConsumedArgs, GetClientSideArgParser, RawArgs,
+            block::BlockArgumentConsumer,
+            bool::BoolArgConsumer,
+            bounded_num::{BoundedNumArgumentConsumer, Number},
+            entities::EntitiesArgumentConsumer,
+            entity::EntityArgumentConsumer,
+            gamemode::GamemodeArgumentConsumer,
+            item::ItemArgumentConsumer,
+            message::MsgArgConsumer,
+            players::PlayersArgumentConsumer,
+            position_3d::Position3DArgumentConsumer,
+            position_block::BlockPosArgumentConsumer,
+            simple::SimpleArgConsumer,
+        },
+        dispatcher::CommandError,
+        tree::{
+            CommandTree,
+            builder::{NonLeafNodeBuilder, argument, literal},
+        },
+    },
+    entity::player::Player,
+    server::Server,
+};
+use pumpkin_protocol::java::client::play::{ArgumentType, CommandSuggestion, SuggestionProviders};
+use pumpkin_util::text::{TextComponent, color::NamedColor};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::SERVER;
+use crate::lua::events::entity::EntityData;
+use crate::lua::permissions::level_number;
+use crate::lua::runtime::{active_plugin, call_as_plugin};
+use crate::lua::worker::LuaCommand;
+use crate::lua::{REQUEST_TIMEOUT, request};
+
+/// The permission checked for Lua commands that do not name their own.
+pub const DEFAULT_PERMISSION_NODE: &str = "plua:command.lua";
+
+/// How long completions wait for the Lua worker while a player types.
+const SUGGESTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Ids of command callbacks, unique across Lua states so a restarted worker
+/// never runs a callback of the state it replaced.
+static NEXT_CALLBACK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Queues the changes Lua plugins make to the server's command dispatcher.
+static DISPATCHER_CHANGES: OnceLock<UnboundedSender<DispatcherChange>> = OnceLock::new();
+
+/// A change to the server's command dispatcher.
+enum DispatcherChange {
+    Register {
+        tree: CommandTree,
+        names: Vec<String>,
+        permission: String,
+    },
+    Unregister {
+        names: Vec<String>,
+    },
+}
+
+/// A command registered by a plugin, under its name and all of its aliases.
+struct RegisteredCommand {
+    owner: Option<String>,
+    names: Vec<String>,
+    /// The ids of the command's `execute` and `suggest` callbacks.
+    callbacks: Vec<u64>,
+}
+
+/// The commands registered in a Lua state, stored as app data. Pumpkin runs
+/// commands on its own tasks, so their nodes only refer to callbacks by id and
+/// the callbacks are run by the Lua worker.
+#[derive(Default)]
+struct Commands {
+    /// Registered commands, keyed by name.
+    registered: HashMap<String, RegisteredCommand>,
+    callbacks: HashMap<u64, Function>,
+}
+
+impl Commands {
+    /// Whether a command registered in this state uses `name` or has it as an alias.
+    fn uses_name(&self, name: &str) -> bool {
+        self.registered
+            .values()
+            .any(|command| command.names.iter().any(|used| used == name))
+    }
+
+    fn remove(&mut self, name: &str) -> Option<RegisteredCommand> {
+        let command = self.registered.remove(name)?;
+        for id in &command.callbacks {
+            self.callbacks.remove(id);
+        }
+        Some(command)
+    }
+}
+
+/// An online player passed to a command callback.
+struct LuaPlayer(Arc<Player>);
+
+impl UserData for LuaPlayer {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.0.gameprofile.name.clone()));
+        fields.add_field_method_get("uuid", |_, this| Ok(this.0.gameprofile.id.to_string()));
+        fields.add_field_method_get("x", |_, this| Ok(this.0.position().x));
+        fields.add_field_method_get("y", |_, this| Ok(this.0.position().y));
+        fields.add_field_method_get("z", |_, this| Ok(this.0.position().z));
+        fields.add_field_method_get("permission_level", |_, this| {
+            Ok(level_number(this.0.permission_lvl.load()))
+        });
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_async_method("has_permission", |_, this, node: String| async move {
+            Ok(this.0.has_permission(&node).await)
+        });
+
+        methods.add_async_method("send_message", |_, this, message: String| async move {
+            this.0
+                .send_system_message(&TextComponent::text(message))
+                .await;
+            Ok(())
+        });
+    }
+}
+
+/// The sender of a command as seen by Lua callbacks.
+#[derive(Clone)]
+pub struct LuaCommandSender {
+    name: String,
+    player: Option<Arc<Player>>,
+    permission_level: u8,
+}
+
+impl LuaCommandSender {
+    fn new(sender: &CommandSender) -> Self {
+        Self {
+            name: sender.to_string(),
+            player: sender.as_player(),
+            permission_level: level_number(sender.permission_lvl()),
+        }
+    }
+}
+
+impl UserData for LuaCommandSender {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.name.clone()));
+        fields.add_field_method_get("is_player", |_, this| Ok(this.player.is_some()));
+        fields.add_field_method_get("uuid", |_, this| {
+            Ok(this
+                .player
+                .as_ref()
+                .map(|player| player.gameprofile.id.to_string()))
+        });
+        fields.add_field_method_get("player", |_, this| Ok(this.player.clone().map(LuaPlayer)));
+        fields.add_field_method_get("permission_level", |_, this| Ok(this.permission_level));
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        // The console and other senders that are not players have every permission.
+        methods.add_async_method("has_permission", |_, this, node: String| async move {
+            Ok(match &this.player {
+                Some(player) => player.has_permission(&node).await,
+                None => true,
+            })
+        });
+
+        methods.add_async_method("send_message", |_, this, message: String| async move {
+            match &this.player {
+                Some(player) => {
+                    player
+                        .send_system_message(&TextComponent::text(message))
+                        .await
+                }
+                None => log::info!("[Lua] {}", message),
+            }
+            Ok(())
+        });
+    }
+}
+
+fn position_table<T: IntoLua>(lua: &Lua, x: T, y: T, z: T) -> mlua::Result<Value> {
+    let position = lua.create_table()?;
+    position.set("x", x)?;
+    position.set("y", y)?;
+    position.set("z", z)?;
+    Ok(Value::Table(position))
+}
+
+fn entity_table(lua: &Lua, entity: &EntityData) -> mlua::Result<Value> {
+    let table = lua.create_table()?;
+    table.set("type", entity.entity_type.as_str())?;
+    table.set("uuid", entity.uuid.as_str())?;
+    table.set("name", entity.name.as_deref())?;
+    Ok(Value::Table(table))
+}
+
+/// A parsed command argument, copied out of Pumpkin's arguments so it can be
+/// sent to the Lua worker.
+#[derive(Clone)]
+pub enum CommandArg {
+    Text(String),
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    BlockPos(i32, i32, i32),
+    Position(f64, f64, f64),
+    Players(Vec<Arc<Player>>),
+    Entities(Vec<EntityData>),
+    Entity(EntityData),
+}
+
+impl CommandArg {
+    fn from_arg(arg: &Arg) -> Option<Self> {
+        Some(match arg {
+            Arg::Simple(value) | Arg::Block(value) | Arg::Item(value) => {
+                Self::Text(value.to_string())
+            }
+            Arg::Msg(value) => Self::Text(value.clone()),
+            Arg::Bool(value) => Self::Bool(*value),
+            Arg::Num(Ok(Number::I32(value))) => Self::Integer((*value).into()),
+            Arg::Num(Ok(Number::I64(value))) => Self::Integer(*value),
+            Arg::Num(Ok(Number::F32(value))) => Self::Number((*value).into()),
+            Arg::Num(Ok(Number::F64(value))) => Self::Number(*value),
+            Arg::GameMode(mode) => Self::Text(format!("{:?}", mode).to_lowercase()),
+            Arg::BlockPos(pos) => Self::BlockPos(pos.0.x, pos.0.y, pos.0.z),
+            Arg::Pos3D(pos) => Self::Position(pos.x, pos.y, pos.z),
+            Arg::Players(players) => Self::Players(players.clone()),
+            Arg::Entities(entities) => Self::Entities(
+                entities
+                    .iter()
+                    .map(|entity| EntityData::from_entity(entity.as_ref()))
+                    .collect(),
+            ),
+            Arg::Entity(entity) => Self::Entity(EntityData::from_entity(entity.as_ref())),
+            _ => return None,
+        })
+    }
+}
+
+/// Players become player objects, other entities tables with their `type`,
+/// `uuid` and `name`.
+impl IntoLua for CommandArg {
+    fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+        match self {
+            Self::Text(value) => value.into_lua(lua),
+            Self::Bool(value) => value.into_lua(lua),
+            Self::Integer(value) => value.into_lua(lua),
+            Self::Number(value) => value.into_lua(lua),
+            Self::BlockPos(x, y, z) => position_table(lua, x, y, z),
+            Self::Position(x, y, z) => position_table(lua, x, y, z),
+            Self::Players(players) => lua
+                .create_sequence_from(players.into_iter().map(LuaPlayer))
+                .map(Value::Table),
+            Self::Entities(entities) => {
+                let table = lua.create_table()?;
+                for entity in &entities {
+                    table.push(entity_table(lua, entity)?)?;
+                }
+                Ok(Value::Table(table))
+            }
+            Self::Entity(entity) => entity_table(lua, &entity),
+        }
+    }
+}
+
+/// Looks up a callback of a registered command. It is gone once the command
+/// was unregistered, or when the worker restarted since the command was run.
+fn command_callback(lua: &Lua, id: u64) -> mlua::Result<Function> {
+    lua.app_data_ref::<Commands>()
+        .and_then(|commands| commands.callbacks.get(&id).cloned())
+        .ok_or_else(|| mlua::Error::RuntimeError("The command is no longer registered".into()))
+}
+
+/// Runs the `execute` callback of a command. Called by the Lua worker.
+pub async fn run_command(
+    lua: &Lua,
+    owner: Option<&str>,
+    callback: u64,
+    sender: LuaCommandSender,
+    arguments: Vec<(String, CommandArg)>,
+) -> mlua::Result<()> {
+    let callback = command_callback(lua, callback)?;
+
+    let args_table = lua.create_table()?;
+    for (name, value) in arguments {
+        args_table.set(name, value)?;
+    }
+
+    let sender = lua.create_userdata(sender)?;
+    call_as_plugin(lua, owner, &callback, (sender, args_table)).await
+}
+
+/// Runs the `suggest` callback of a command argument. Called by the Lua worker.
+pub async fn suggest_command(
+    lua: &Lua,
+    owner: Option<&str>,
+    callback: u64,
+    sender: LuaCommandSender,
+    partial: String,
+) -> mlua::Result<Vec<String>> {
+    let callback = command_callback(lua, callback)?;
+    let sender = lua.create_userdata(sender)?;
+    call_as_plugin(lua, owner, &callback, (sender, partial)).await
+}
+
+/// Runs the `execute` callback of a node in a Lua-registered command.
+struct LuaCommandExecutor {
+    owner: Option<String>,
+    callback: u64,
+    /// The names of the arguments on the path from the root to this node.
+    arguments: Vec<String>,
+}
+
+impl LuaCommandExecutor {
+    /// Returns the name of a numeric argument outside of its bounds.
+    fn out_of_bounds(&self, args: &ConsumedArgs<'_>) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|name| matches!(args.get(name.as_str()), Some(Arg::Num(Err(_)))))
+            .map(String::as_str)
+    }
+
+    /// Has the Lua worker run the callback, so it is attributed and budgeted
+    /// like any other plugin code.
+    async fn call(&self, sender: &CommandSender, args: &ConsumedArgs<'_>) -> anyhow::Result<()> {
+        let arguments = self
+            .arguments
+            .iter()
+            .filter_map(|name| {
+                let value = CommandArg::from_arg(args.get(name.as_str())?)?;
+                Some((name.clone(), value))
+            })
+            .collect();
+
+        request(REQUEST_TIMEOUT, |response| LuaCommand::RunCommand {
+            owner: self.owner.clone(),
+            callback: self.callback,
+            sender: LuaCommandSender::new(sender),
+            arguments,
+            response,
+        })
+        .await??;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for LuaCommandExecutor {
+    async fn execute<'a>(
+        &self,
+        sender: &mut CommandSender,
+        _: &Server,
+        args: &ConsumedArgs<'a>,
+    ) -> Result<(), CommandError> {
+        if let Some(name) = self.out_of_bounds(args) {
+            sender
+                .send_message(
+                    TextComponent::text(format!("The value of {} is out of range.", name))
+                        .color_named(NamedColor::Red),
+                )
+                .await;
+            return Ok(());
+        }
+
+        if let Err(e) = self.call(sender, args).await {
+            log::error!(
+                "Error in command of plugin {}: {}",
+                self.owner.as_deref().unwrap_or("unknown"),
+                e
+            );
+
+            sender
+                .send_message(
+                    TextComponent::text("An error occurred while running this command.")
+                        .color_named(NamedColor::Red),
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+}
+
+/// The `suggest` callback of an argument node, offering completions for it.
+struct LuaSuggestions {
+    owner: Option<String>,
+    callback: u64,
+}
+
+impl LuaSuggestions {
+    /// Returns the suggestions starting with what the sender typed so far.
+    async fn call(
+        &self,
+        sender: &CommandSender,
+        partial: &str,
+    ) -> anyhow::Result<Vec<CommandSuggestion>> {
+        let suggestions = request(SUGGESTION_TIMEOUT, |response| LuaCommand::SuggestCommand {
+            owner: self.owner.clone(),
+            callback: self.callback,
+            sender: LuaCommandSender::new(sender),
+            partial: partial.to_string(),
+            response,
+        })
+        .await??;
+
+        Ok(suggestions
+            .into_iter()
+            .filter(|suggestion| suggestion.starts_with(partial))
+            .map(|suggestion| CommandSuggestion::new(suggestion, None))
+            .collect())
+    }
+}
+
+/// Parses an argument with another consumer, but has the client ask the server
+/// for completions, which come from a Lua `suggest` callback.
+struct SuggestingConsumer<C> {
+    inner: C,
+    suggestions: LuaSuggestions,
+}
+
+impl<C: GetClientSideArgParser> GetClientSideArgParser for SuggestingConsumer<C> {
+    fn get_client_side_parser(&self) -> ArgumentType {
+        self.inner.get_client_side_parser()
+    }
+
+    fn get_client_side_suggestion_type_override(&self) -> Option<SuggestionProviders> {
+        Some(SuggestionProviders::AskServer)
+    }
+}
+
+#[async_trait]
+impl<C: ArgumentConsumer> ArgumentConsumer for SuggestingConsumer<C> {
+    async fn consume<'a>(
+        &'a self,
+        sender: &CommandSender,
+        server: &'a Server,
+        args: &mut RawArgs<'a>,
+    ) -> Option<Arg<'a>> {
+        self.inner.consume(sender, server, args).await
+    }
+
+    async fn suggest<'a>(
+        &'a self,
+        sender: &CommandSender,
+        _server: &'a Server,
+        input: &'a str,
+    ) -> Result<Option<Vec<CommandSuggestion>>, CommandError> {
+        match self.suggestions.call(sender, input).await {
+            Ok(suggestions) => Ok(Some(suggestions)),
+            Err(e) => {
+                log::error!(
+                    "Error in command suggestions of plugin {}: {}",
+                    self.suggestions.owner.as_deref().unwrap_or("unknown"),
+                    e
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Collects the callbacks of a command while its tree is built.
+struct TreeBuilder {
+    owner: Option<String>,
+    callbacks: HashMap<u64, Function>,
+}
+
+impl TreeBuilder {
+    /// Keeps a callback and returns the id its node refers to it by.
+    fn callback(&mut self, function: Function) -> u64 {
+        let id = NEXT_CALLBACK_ID.fetch_add(1, Ordering::Relaxed);
+        self.callbacks.insert(id, function);
+        id
+    }
+}
+
+/// Builds the executor for the `execute` field of a command or node table.
+fn executor(
+    builder: &mut TreeBuilder,
+    spec: &Table,
+    arguments: &[String],
+) -> mlua::Result<Option<LuaCommandExecutor>> {
+    Ok(spec
+        .get::<Option<Function>>("execute")?
+        .map(|callback| LuaCommandExecutor {
+            owner: builder.owner.clone(),
+            callback: builder.callback(callback),
+            arguments: arguments.to_vec(),
+        }))
+}
+
+/// Creates an argument node, completed by `suggestions` if there are any.
+fn argument_with<C: ArgumentConsumer + 'static>(
+    name: &str,
+    consumer: C,
+    suggestions: Option<LuaSuggestions>,
+) -> NonLeafNodeBuilder {
+    match suggestions {
+        Some(suggestions) => argument(
+            name,
+            SuggestingConsumer {
+                inner: consumer,
+                suggestions,
+            },
+        ),
+        None => argument(name, consumer),
+    }
+}
+
+/// Creates an argument node parsing the `type` of the node table, with the
+/// `min` and `max` bounds for numbers and completions from `suggest`.
+fn argument_node(
+    builder: &mut TreeBuilder,
+    name: &str,
+    spec: &Table,
+) -> mlua::Result<NonLeafNodeBuilder> {
+    let suggestions = spec
+        .get::<Option<Function>>("suggest")?
+        .map(|callback| LuaSuggestions {
+            owner: builder.owner.clone(),
+            callback: builder.callback(callback),
+        });
+
+    let kind = spec
+        .get::<Option<String>>("type")?
+        .unwrap_or_else(|| "word".into());
+
+    Ok(match kind.as_str() {
+        "word" => argument_with(name, SimpleArgConsumer, suggestions),
+        "message" => argument_with(name, MsgArgConsumer, suggestions),
+        "bool" => argument_with(name, BoolArgConsumer, suggestions),
+        "int" => {
+            let mut consumer = BoundedNumArgumentConsumer::<i32>::new();
+            if let Some(min) = spec.get::<Option<i32>>("min")? {
+                consumer = consumer.min(min);
+            }
+            if let Some(max) = spec.get::<Option<i32>>("max")? {
+                consumer = consumer.max(max);
+            }
+            argument_with(name, consumer, suggestions)
+        }
+        "float" => {
+            let mut consumer = BoundedNumArgumentConsumer::<f64>::new();
+            if let Some(min) = spec.get::<Option<f64>>("min")? {
+                consumer = consumer.min(min);
+            }
+            if let Some(max) = spec.get::<Option<f64>>("max")? {
+                consumer = consumer.max(max);
+            }
+            argument_with(name, consumer, suggestions)
+        }
+        "players" => argument_with(name, PlayersArgumentConsumer, suggestions),
+        "entities" => argument_with(name, EntitiesArgumentConsumer, suggestions),
+        "entity" => argument_with(name, EntityArgumentConsumer, suggestions),
+        "block_pos" => argument_with(name, BlockPosArgumentConsumer, suggestions),
+        "position" => argument_with(name, Position3DArgumentConsumer, suggestions),
+        "block" => argument_with(name, BlockArgumentConsumer, suggestions),
+        "item" => argument_with(name, ItemArgumentConsumer, suggestions),
+        "gamemode" => argument_with(name, GamemodeArgumentConsumer, suggestions),
+        _ => {
+            return Err(mlua::Error::RuntimeError(format!(
+                "Unknown argument type: {}",
+                kind
+            )));
+        }
+    })
+}
+
+/// Builds a `literal` or `argument` node and its children.
+fn build_node(
+    builder: &mut TreeBuilder,
+    spec: &Table,
+    arguments: &[String],
+) -> mlua::Result<NonLeafNodeBuilder> {
+    let mut arguments = arguments.to_vec();
+
+    let mut node = if let Some(name) = spec.get::<Option<String>>("literal")? {
+        literal(name)
+    } else if let Some(name) = spec.get::<Option<String>>("argument")? {
+        let node = argument_node(builder, &name, spec)?;
+        arguments.push(name);
+        node
+    } else {
+        return Err(mlua::Error::RuntimeError(
+            "Command nodes need a literal or argument name".into(),
+        ));
+    };
+
+    if let Some(executor) = executor(builder, spec, &arguments)? {
+        node = node.execute(executor);
+    }
+
+    for child in children(spec, "children")? {
+        node = node.then(build_node(builder, &child, &arguments)?);
+    }
+
+    Ok(node)
+}
+
+fn children(spec: &Table, key: &str) -> mlua::Result<Vec<Table>> {
+    spec.get::<Option<Table>>(key)?
+        .map(|nodes| nodes.sequence_values::<Table>().collect())
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Builds the `CommandTree` described by the table passed to `pumpkin.commands.register`.
+fn build_tree(
+    builder: &mut TreeBuilder,
+    spec: &Table,
+    names: &[String],
+) -> mlua::Result<CommandTree> {
+    let description = spec
+        .get::<Option<String>>("description")?
+        .unwrap_or_default();
+
+    let mut tree = CommandTree::new(names.to_vec(), description);
+
+    if let Some(executor) = executor(builder, spec, &[])? {
+        tree = tree.execute(executor);
+    }
+
+    for node in children(spec, "tree")? {
+        tree = tree.then(build_node(builder, &node, &[])?);
+    }
+
+    Ok(tree)
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-".contains(c))
+}
+
+/// Queues a change to the server's command dispatcher.
+///
+/// Pumpkin holds a read guard on the dispatcher while a command runs, and the
+/// worker handles requests from running commands, such as `/plua disable` or a
+/// Lua command callback. Taking the write lock in the worker would wait for
+/// the very command it answers, so the changes are applied in order by a task
+/// of their own.
+fn queue_dispatcher_change(change: DispatcherChange) {
+    let changes = DISPATCHER_CHANGES.get_or_init(|| {
+        let (changes, rx) = unbounded_channel();
+        tokio::spawn(apply_dispatcher_changes(rx));
+        changes
+    });
+
+    if changes.send(change).is_err() {
+        log::error!("Failed to queue a change to the command dispatcher");
+    }
+}
+
+async fn apply_dispatcher_changes(mut changes: UnboundedReceiver<DispatcherChange>) {
+    // The names Lua commands were registered under, so a command whose name
+    // turned out to be taken never unregisters the command that took it
+    let mut registered = HashSet::new();
+
+    while let Some(change) = changes.recv().await {
+        let Some(server) = SERVER.get() else {
+            continue;
+        };
+        let mut dispatcher = server.command_dispatcher.write().await;
+
+        match change {
+            DispatcherChange::Register {
+                tree,
+                names,
+                permission,
+            } => {
+                if let Some(taken) = names.iter().find(|name| dispatcher.get_tree(name).is_ok()) {
+                    log::error!(
+                        "Failed to register Lua command /{}: the name is already registered",
+                        taken
+                    );
+                    continue;
+                }
+
+                registered.extend(names);
+                dispatcher.register(tree, &permission);
+            }
+            DispatcherChange::Unregister { names } => {
+                for name in names {
+                    if registered.remove(&name) {
+                        dispatcher.unregister(&name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Creates the `pumpkin.commands` table.
+pub fn setup_lua_api(lua: &Lua, pumpkin_table: &Table) -> mlua::Result<()> {
+    lua.set_app_data(Commands::default());
+
+    let commands_table = lua.create_table()?;
+
+    commands_table.set(
+        "register",
+        lua.create_function(|lua_ctx, spec: Table| {
+            let name: String = spec.get("name")?;
+            let aliases: Vec<String> = spec
+                .get::<Option<Vec<String>>>("aliases")?
+                .unwrap_or_default();
+            let permission = spec
+                .get::<Option<String>>("permission")?
+                .unwrap_or_else(|| DEFAULT_PERMISSION_NODE.into());
+
+            let names: Vec<String> = std::iter::once(name.clone()).chain(aliases).collect();
+            if let Some(invalid) = names.iter().find(|name| !valid_name(name)) {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Invalid command name: {}",
+                    invalid
+                )));
+            }
+
+            let mut builder = TreeBuilder {
+                owner: active_plugin(lua_ctx),
+                callbacks: HashMap::new(),
+            };
+            let tree = build_tree(&mut builder, &spec, &names)?;
+
+            let Some(server) = SERVER.get() else {
+                return Err(mlua::Error::RuntimeError(
+                    "Commands can only be registered once the server is running".into(),
+                ));
+            };
+
+            // The dispatcher is locked while a command runs, so this check is
+            // skipped then and the dispatcher task checks the names again
+            let server_names: Vec<String> = match server.command_dispatcher.try_read() {
+                Ok(dispatcher) => names
+                    .iter()
+                    .filter(|name| dispatcher.get_tree(name).is_ok())
+                    .cloned()
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+
+            let mut commands = lua_ctx
+                .app_data_mut::<Commands>()
+                .ok_or_else(|| mlua::Error::RuntimeError("Commands not initialized".into()))?;
+
+            let taken = names
+                .iter()
+                .find(|name| commands.uses_name(name) || server_names.contains(name));
+            if let Some(taken) = taken {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Command /{} is already registered",
+                    taken
+                )));
+            }
+
+            commands.registered.insert(
+                name.clone(),
+                RegisteredCommand {
+                    owner: builder.owner,
+                    names: names.clone(),
+                    callbacks: builder.callbacks.keys().copied().collect(),
+                },
+            );
+            commands.callbacks.extend(builder.callbacks);
+
+            queue_dispatcher_change(DispatcherChange::Register {
+                tree,
+                names,
+                permission,
+            });
+
+            Ok(())
+        })?,
+    )?;
+
+    commands_table.set(
+        "unregister",
+        lua.create_function(|lua_ctx, name: String| {
+            // Plugins can only unregister their own commands
+            let owner = active_plugin(lua_ctx);
+            let Some(command) = lua_ctx.app_data_mut::<Commands>().and_then(|mut commands| {
+                let owned = commands
+                    .registered
+                    .get(&name)
+                    .is_some_and(|command| command.owner == owner);
+                if owned { commands.remove(&name) } else { None }
+            }) else {
+                return Ok(false);
+            };
+
+            queue_dispatcher_change(DispatcherChange::Unregister {
+                names: command.names,
+            });
+            Ok(true)
+        })?,
+    )?;
+
+    pumpkin_table.set("commands", commands_table)?;
+
+    Ok(())
+}
+
+/// Unregisters every command registered by a plugin, returning how many were removed.
+pub fn remove_plugin_commands(lua: &Lua, plugin_name: &str) -> usize {
+    let removed: Vec<RegisteredCommand> = {
+        let Some(mut commands) = lua.app_data_mut::<Commands>() else {
+            return 0;
+        };
+
+        let names: Vec<String> = commands
+            .registered
+            .iter()
+            .filter(|(_, command)| command.owner.as_deref() == Some(plugin_name))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        names
+            .iter()
+            .filter_map(|name| commands.remove(name))
+            .collect()
+    };
+
+    let count = removed.len();
+    for command in removed {
+        queue_dispatcher_change(DispatcherChange::Unregister {
+            names: command.names,
+        });
+    }
+
+    count
+}
+
+/// Unregisters every command registered in a Lua state, returning how many were removed.
+pub fn remove_all_commands(lua: &Lua) -> usize {
+    let removed: Vec<RegisteredCommand> = match lua.app_data_mut::<Commands>() {
+        Some(mut commands) => {
+            commands.callbacks.clear();
+            commands
+                .registered
+                .drain()
+                .map(|(_, command)| command)
+                .collect()
+        }
+        None => return 0,
+    };
+
+    let count = removed.len();
+    for command in removed {
+        queue_dispatcher_change(DispatcherChange::Unregister {
+            names: command.names,
+        });
+    }
+
+    count
+}
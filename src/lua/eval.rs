@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use mlua::{Lua, MultiValue, Table, Value};
+
+use crate::lua::runtime::{call_as_plugin, plugin_environment};
+
+/// How deep nested tables are printed before being elided.
+const MAX_DEPTH: usize = 3;
+
+/// How many entries of a table are printed before the rest are elided.
+const MAX_ENTRIES: usize = 50;
+
+/// Runs a snippet in the environment of `plugin_name` and returns its results,
+/// pretty-printed one per line. Expressions are returned without `return`.
+pub async fn eval(lua: &Lua, plugin_name: &str, code: &str) -> mlua::Result<String> {
+    let env = plugin_environment(lua, plugin_name)?;
+
+    let expression = format!("return {}", code);
+    let chunk = match lua
+        .load(&expression)
+        .set_name("=eval")
+        .set_environment(env.clone())
+        .into_function()
+    {
+        Ok(function) => function,
+        Err(_) => lua
+            .load(code)
+            .set_name("=eval")
+            .set_environment(env)
+            .into_function()?,
+    };
+
+    let values: MultiValue = call_as_plugin(lua, Some(plugin_name), &chunk, ()).await?;
+
+    if values.is_empty() {
+        return Ok("nil".into());
+    }
+
+    let mut output = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        pretty(&mut output, value, 0, &mut HashSet::new());
+    }
+    Ok(output)
+}
+
+fn pretty(output: &mut String, value: &Value, depth: usize, seen: &mut HashSet<usize>) {
+    match value {
+        Value::String(s) => {
+            let _ = write!(output, "{:?}", s.to_string_lossy());
+        }
+        Value::Table(table) => pretty_table(output, table, depth, seen),
+        Value::Nil => output.push_str("nil"),
+        Value::Boolean(b) => {
+            let _ = write!(output, "{}", b);
+        }
+        Value::Integer(i) => {
+            let _ = write!(output, "{}", i);
+        }
+        Value::Number(n) => {
+            let _ = write!(output, "{}", n);
+        }
+        other => {
+            let _ = write!(output, "{}: {:?}", other.type_name(), other.to_pointer());
+        }
+    }
+}
+
+fn pretty_table(output: &mut String, table: &Table, depth: usize, seen: &mut HashSet<usize>) {
+    let address = table.to_pointer() as usize;
+    if !seen.insert(address) {
+        output.push_str("<cycle>");
+        return;
+    }
+
+    if depth >= MAX_DEPTH {
+        output.push_str("{ ... }");
+        seen.remove(&address);
+        return;
+    }
+
+    let mut entries: Vec<(Value, Value)> = table.pairs().filter_map(Result::ok).collect();
+    if entries.is_empty() {
+        output.push_str("{}");
+        seen.remove(&address);
+        return;
+    }
+
+    entries.sort_by_key(|(key, _)| sort_key(key));
+
+    let indent = "  ".repeat(depth + 1);
+    output.push_str("{\n");
+    for (key, value) in entries.iter().take(MAX_ENTRIES) {
+        output.push_str(&indent);
+        match key {
+            Value::String(name) => output.push_str(&name.to_string_lossy()),
+            key => {
+                output.push('[');
+                pretty(output, key, depth + 1, seen);
+                output.push(']');
+            }
+        }
+        output.push_str(" = ");
+        pretty(output, value, depth + 1, seen);
+        output.push_str(",\n");
+    }
+    if entries.len() > MAX_ENTRIES {
+        let _ = writeln!(output, "{}... {} more", indent, entries.len() - MAX_ENTRIES);
+    }
+    output.push_str(&"  ".repeat(depth));
+    output.push('}');
+
+    seen.remove(&address);
+}
+
+/// Orders array entries first by index, then named entries by name.
+fn sort_key(key: &Value) -> (u8, i64, String) {
+    match key {
+        Value::Integer(i) => (0, (*i).into(), String::new()),
+        Value::Number(n) => (0, *n as i64, String::new()),
+        Value::String(s) => (1, 0, s.to_string_lossy()),
+        other => (2, 0, format!("{:?}", other.to_pointer())),
+    }
+}
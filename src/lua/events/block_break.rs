@@ -1,16 +1,22 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{IntoLua, Lua, Table, Value};
 use pumpkin::{
-    plugin::{Context, EventHandler, EventPriority, block::block_break::BlockBreakEvent},
+    plugin::{Cancellable, Context, EventHandler, block::block_break::BlockBreakEvent},
     server::Server,
 };
 use pumpkin_api_macros::with_runtime;
 
-use crate::lua::worker::{LuaCommand, send_event_command};
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
 
-pub struct BlockBreakEventHandler;
+pub struct BlockBreakEventHandler {
+    tiers: &'static [ListenerPriority],
+}
 
 #[with_runtime(global)]
 #[async_trait]
@@ -25,18 +31,23 @@ impl EventHandler<BlockBreakEvent> for BlockBreakEventHandler {
             position_z: event.block_position.0.z,
             experience: event.exp,
             drop_items: event.drop,
+            cancelled: event.cancelled(),
         };
 
-        if let Err(e) = send_event_command(LuaCommand::TriggerEvent {
-            event_type: "block_break".to_string(),
-            event_data: serde_json::to_string(&event_data).unwrap_or_default(),
-        }) {
-            log::error!("Failed to send block break event to Lua: {}", e);
+        let outcome = dispatch_event(&BlockBreak, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.exp = modified.experience;
+            event.drop = modified.drop_items;
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
 struct EventData {
     player_name: Option<String>,
     player_uuid: Option<String>,
@@ -46,72 +57,67 @@ struct EventData {
     position_z: i32,
     experience: u32,
     drop_items: bool,
+    cancelled: bool,
 }
 
-pub async fn register(context: &Context) -> Result<(), String> {
-    context
-        .register_event(
-            Arc::new(BlockBreakEventHandler),
-            EventPriority::Lowest,
-            true,
-        )
-        .await;
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+    const MUTABLE_FIELDS: &'static [&'static str] = &["experience", "drop_items"];
 
-    Ok(())
-}
-
-pub fn setup_lua_event(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
-    let block_break_listeners = lua.create_table()?;
-    events_table.set("block_break", block_break_listeners)?;
-
-    Ok(())
-}
-
-pub fn trigger_event(lua: &Lua, event_data_json: &str) -> mlua::Result<()> {
-    let event_data: EventData = match serde_json::from_str(event_data_json) {
-        Ok(data) => data,
-        Err(e) => {
-            log::error!("Failed to parse block break event data: {}", e);
-            return Ok(());
-        }
-    };
-
-    let globals = lua.globals();
-    let pumpkin: Table = globals.get("pumpkin")?;
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
 
-    let events: Table = match pumpkin.get("events") {
-        Ok(events) => events,
-        Err(_) => {
-            return Ok(());
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_deref().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_deref().into_lua(lua),
+            "block_type" => self.block_type.as_str().into_lua(lua),
+            "position_x" => self.position_x.into_lua(lua),
+            "position_y" => self.position_y.into_lua(lua),
+            "position_z" => self.position_z.into_lua(lua),
+            "experience" => self.experience.into_lua(lua),
+            "drop_items" => self.drop_items.into_lua(lua),
+            _ => Ok(Value::Nil),
         }
-    };
+    }
 
-    let block_break_listeners: Table = match events.get("block_break") {
-        Ok(listeners) => listeners,
-        Err(_) => {
-            return Ok(());
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        match key {
+            "experience" => self.experience = lua.unpack(value)?,
+            "drop_items" => self.drop_items = lua.unpack(value)?,
+            _ => {}
         }
-    };
 
-    let event_table = lua.create_table()?;
-    if let Some(player_name) = &event_data.player_name {
-        event_table.set("player_name", player_name.clone())?;
+        Ok(())
     }
-    if let Some(player_uuid) = &event_data.player_uuid {
-        event_table.set("player_uuid", player_uuid.clone())?;
+}
+
+pub struct BlockBreak;
+
+#[async_trait]
+impl LuaEvent for BlockBreak {
+    fn name(&self) -> &'static str {
+        "block_break"
     }
-    event_table.set("block_type", event_data.block_type)?;
-    event_table.set("position_x", event_data.position_x)?;
-    event_table.set("position_y", event_data.position_y)?;
-    event_table.set("position_z", event_data.position_z)?;
-    event_table.set("experience", event_data.experience)?;
-    event_table.set("drop_items", event_data.drop_items)?;
-
-    for (_, callback) in block_break_listeners.pairs::<Value, Function>().flatten() {
-        if let Err(e) = callback.call::<()>(event_table.clone()) {
-            log::error!("Error in block_break event handler: {}", e);
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(BlockBreakEventHandler { tiers }), priority, true)
+                .await;
         }
+
+        Ok(())
     }
 
-    Ok(())
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
 }
@@ -1,16 +1,22 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{IntoLua, Lua, Table, Value};
 use pumpkin::{
-    plugin::{Context, EventHandler, EventPriority, block::block_place::BlockPlaceEvent},
+    plugin::{Cancellable, Context, EventHandler, block::block_place::BlockPlaceEvent},
     server::Server,
 };
 use pumpkin_api_macros::with_runtime;
 
-use crate::lua::worker::{LuaCommand, send_event_command};
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
 
-pub struct BlockPlaceEventHandler;
+pub struct BlockPlaceEventHandler {
+    tiers: &'static [ListenerPriority],
+}
 
 #[with_runtime(global)]
 #[async_trait]
@@ -22,83 +28,84 @@ impl EventHandler<BlockPlaceEvent> for BlockPlaceEventHandler {
             block_placed: event.block_placed.name.to_string(),
             block_against: event.block_placed_against.name.to_string(),
             can_build: event.can_build,
+            cancelled: event.cancelled(),
         };
 
-        if let Err(e) = send_event_command(LuaCommand::TriggerEvent {
-            event_type: "block_place".to_string(),
-            event_data: serde_json::to_string(&event_data).unwrap_or_default(),
-        }) {
-            log::error!("Failed to send block place event to Lua: {}", e);
+        let outcome = dispatch_event(&BlockPlace, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.can_build = modified.can_build;
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
 struct EventData {
     player_name: String,
     player_uuid: String,
     block_placed: String,
     block_against: String,
     can_build: bool,
+    cancelled: bool,
 }
 
-pub async fn register(context: &Context) -> Result<(), String> {
-    context
-        .register_event(
-            Arc::new(BlockPlaceEventHandler),
-            EventPriority::Lowest,
-            true,
-        )
-        .await;
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+    const MUTABLE_FIELDS: &'static [&'static str] = &["can_build"];
 
-    Ok(())
-}
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "block_placed" => self.block_placed.as_str().into_lua(lua),
+            "block_against" => self.block_against.as_str().into_lua(lua),
+            "can_build" => self.can_build.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
 
-pub fn setup_lua_event(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
-    let block_place_listeners = lua.create_table()?;
-    events_table.set("block_place", block_place_listeners)?;
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        if key == "can_build" {
+            self.can_build = lua.unpack(value)?;
+        }
 
-    Ok(())
+        Ok(())
+    }
 }
 
-pub fn trigger_event(lua: &Lua, event_data_json: &str) -> mlua::Result<()> {
-    let event_data: EventData = match serde_json::from_str(event_data_json) {
-        Ok(data) => data,
-        Err(e) => {
-            log::error!("Failed to parse block place event data: {}", e);
-            return Ok(());
-        }
-    };
+pub struct BlockPlace;
 
-    let globals = lua.globals();
-    let pumpkin: Table = globals.get("pumpkin")?;
+#[async_trait]
+impl LuaEvent for BlockPlace {
+    fn name(&self) -> &'static str {
+        "block_place"
+    }
 
-    let events: Table = match pumpkin.get("events") {
-        Ok(events) => events,
-        Err(_) => {
-            return Ok(());
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(BlockPlaceEventHandler { tiers }), priority, true)
+                .await;
         }
-    };
 
-    let block_place_listeners: Table = match events.get("block_place") {
-        Ok(listeners) => listeners,
-        Err(_) => {
-            return Ok(());
-        }
-    };
-
-    let event_table = lua.create_table()?;
-    event_table.set("player_name", event_data.player_name)?;
-    event_table.set("player_uuid", event_data.player_uuid)?;
-    event_table.set("block_placed", event_data.block_placed)?;
-    event_table.set("block_against", event_data.block_against)?;
-    event_table.set("can_build", event_data.can_build)?;
-
-    for (_, callback) in block_place_listeners.pairs::<Value, Function>().flatten() {
-        if let Err(e) = callback.call::<()>(event_table.clone()) {
-            log::error!("Error in block_place event handler: {}", e);
-        }
+        Ok(())
     }
 
-    Ok(())
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
 }
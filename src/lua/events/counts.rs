@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use crate::lua::events::priority::ListenerPriority;
+
+/// How many Lua listeners each event has per priority, across every Lua
+/// runtime. Event handlers check it so events nobody listens to never leave
+/// the Pumpkin thread.
+static COUNTS: LazyLock<RwLock<HashMap<String, [usize; ListenerPriority::ALL.len()]>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub fn add(event_type: &str, priority: ListenerPriority) {
+    let mut counts = COUNTS.write().unwrap();
+    counts.entry(event_type.to_string()).or_default()[priority as usize] += 1;
+}
+
+pub fn remove(event_type: &str, priority: ListenerPriority) {
+    let mut counts = COUNTS.write().unwrap();
+    if let Some(count) = counts.get_mut(event_type) {
+        count[priority as usize] = count[priority as usize].saturating_sub(1);
+    }
+}
+
+/// Forgets every listener, for when the Lua state they were registered in is
+/// abandoned.
+pub fn clear() {
+    COUNTS.write().unwrap().clear();
+}
+
+/// Whether any listener is registered for an event in one of the given tiers.
+pub fn any(event_type: &str, tiers: &[ListenerPriority]) -> bool {
+    COUNTS
+        .read()
+        .unwrap()
+        .get(event_type)
+        .is_some_and(|count| tiers.iter().any(|tier| count[*tier as usize] > 0))
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeSet;
+
+use mlua::{Lua, Table, Value};
+
+use crate::lua::events::{
+    add_listener_table, call_cancellable_listeners, find, listener_table, make_cancellable,
+    priority::ListenerPriority,
+};
+
+/// The names of the events defined by plugins in a Lua state, stored as app data.
+#[derive(Default)]
+struct CustomEvents(BTreeSet<String>);
+
+pub fn is_defined(lua: &Lua, name: &str) -> bool {
+    lua.app_data_ref::<CustomEvents>()
+        .is_some_and(|events| events.0.contains(name))
+}
+
+pub fn defined_names(lua: &Lua) -> Vec<String> {
+    lua.app_data_ref::<CustomEvents>()
+        .map(|events| events.0.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Adds `define` and `emit` to the `pumpkin.events` table.
+///
+/// Definitions are not owned by the defining plugin: defining an existing event
+/// does nothing, so a plugin can be reloaded without dropping the listeners
+/// other plugins registered for its events.
+pub fn setup_lua_api(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
+    lua.set_app_data(CustomEvents::default());
+
+    events_table.set(
+        "define",
+        lua.create_function(|lua_ctx, name: String| {
+            if find(&name).is_some() {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Cannot redefine built-in event: {}",
+                    name
+                )));
+            }
+
+            if is_defined(lua_ctx, &name) {
+                return Ok(false);
+            }
+
+            add_listener_table(lua_ctx, &name)?;
+
+            if let Some(mut defined) = lua_ctx.app_data_mut::<CustomEvents>() {
+                defined.0.insert(name);
+            }
+
+            Ok(true)
+        })?,
+    )?;
+
+    events_table.set(
+        "emit",
+        lua.create_async_function(
+            |lua_ctx, (name, payload): (String, Option<Table>)| async move {
+                if !is_defined(&lua_ctx, &name) {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Unknown custom event: {}",
+                        name
+                    )));
+                }
+
+                let listeners = listener_table(&lua_ctx, &name)?;
+
+                // The payload is copied so the caller's table is left as it was
+                let event_table = lua_ctx.create_table()?;
+                if let Some(payload) = payload {
+                    for pair in payload.pairs::<Value, Value>() {
+                        let (key, value) = pair?;
+                        event_table.raw_set(key, value)?;
+                    }
+                }
+
+                make_cancellable(&lua_ctx, &event_table)?;
+
+                call_cancellable_listeners(
+                    &lua_ctx,
+                    &name,
+                    &listeners,
+                    &event_table,
+                    &ListenerPriority::ALL,
+                )
+                .await
+            },
+        )?,
+    )?;
+
+    Ok(())
+}
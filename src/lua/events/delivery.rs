@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static SENT: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static SHED: AtomicU64 = AtomicU64::new(0);
+static TIMED_OUT: AtomicU64 = AtomicU64::new(0);
+
+/// Counters describing how well events reach the Lua worker since startup.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeliveryStats {
+    /// Events sent to the Lua worker.
+    pub sent: u64,
+    /// Commands a Lua runtime missed because it fell too far behind.
+    pub dropped: u64,
+    /// Fire-and-forget events not sent because the worker was backlogged.
+    pub shed: u64,
+    /// Events whose listeners did not answer within the response timeout.
+    pub timed_out: u64,
+}
+
+pub fn record_sent() {
+    SENT.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_dropped(count: u64) {
+    DROPPED.fetch_add(count, Ordering::Relaxed);
+}
+
+pub fn record_shed() {
+    SHED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_timeout() {
+    TIMED_OUT.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn stats() -> DeliveryStats {
+    DeliveryStats {
+        sent: SENT.load(Ordering::Relaxed),
+        dropped: DROPPED.load(Ordering::Relaxed),
+        shed: SHED.load(Ordering::Relaxed),
+        timed_out: TIMED_OUT.load(Ordering::Relaxed),
+    }
+}
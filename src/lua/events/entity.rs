@@ -0,0 +1,37 @@
+use mlua::{IntoLua, Lua, Value};
+use pumpkin::entity::EntityBase;
+
+/// Identifies an entity involved in an event.
+#[derive(Clone)]
+pub struct EntityData {
+    pub entity_type: String,
+    pub uuid: String,
+    pub name: Option<String>,
+}
+
+impl EntityData {
+    pub fn from_entity(entity: &dyn EntityBase) -> Self {
+        let base = entity.get_entity();
+
+        Self {
+            entity_type: base.entity_type.resource_name.to_string(),
+            uuid: base.entity_uuid.to_string(),
+            name: entity
+                .get_player()
+                .map(|player| player.gameprofile.name.clone()),
+        }
+    }
+
+    /// Reads `<prefix>_type`, `<prefix>_uuid` or, for players, `<prefix>_name`.
+    /// Returns `None` if the key is not one of these fields.
+    pub fn field(&self, lua: &Lua, prefix: &str, key: &str) -> Option<mlua::Result<Value>> {
+        let field = key.strip_prefix(prefix)?.strip_prefix('_')?;
+
+        Some(match field {
+            "type" => self.entity_type.as_str().into_lua(lua),
+            "uuid" => self.uuid.as_str().into_lua(lua),
+            "name" => self.name.as_deref().into_lua(lua),
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    plugin::{Cancellable, Context, EventHandler, entity::entity_damage::EntityDamageEvent},
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    entity::EntityData,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
+
+pub struct EntityDamageEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<EntityDamageEvent> for EntityDamageEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut EntityDamageEvent) {
+        let event_data = EventData {
+            victim: EntityData::from_entity(event.entity.as_ref()),
+            attacker: event
+                .attacker
+                .as_ref()
+                .map(|attacker| EntityData::from_entity(attacker.as_ref())),
+            cause: event.damage_type.message_id.to_string(),
+            amount: event.damage,
+            cancelled: event.cancelled(),
+        };
+
+        let outcome = dispatch_event(&EntityDamage, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.damage = modified.amount.max(0.0);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EventData {
+    victim: EntityData,
+    attacker: Option<EntityData>,
+    cause: String,
+    amount: f32,
+    cancelled: bool,
+}
+
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+    const MUTABLE_FIELDS: &'static [&'static str] = &["amount"];
+
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        if let Some(value) = self.victim.field(lua, "victim", key) {
+            return value;
+        }
+
+        if let Some(value) = self
+            .attacker
+            .as_ref()
+            .and_then(|attacker| attacker.field(lua, "attacker", key))
+        {
+            return value;
+        }
+
+        match key {
+            "cause" => self.cause.as_str().into_lua(lua),
+            "amount" => self.amount.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        if key == "amount" {
+            self.amount = lua.unpack(value)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct EntityDamage;
+
+#[async_trait]
+impl LuaEvent for EntityDamage {
+    fn name(&self) -> &'static str {
+        "entity_damage"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(EntityDamageEventHandler { tiers }), priority, true)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
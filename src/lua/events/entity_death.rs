@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    plugin::{Context, EventHandler, entity::entity_death::EntityDeathEvent},
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    entity::EntityData,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
+
+pub struct EntityDeathEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<EntityDeathEvent> for EntityDeathEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut EntityDeathEvent) {
+        let event_data = EventData {
+            entity: EntityData::from_entity(event.entity.as_ref()),
+            cause: event.damage_type.message_id.to_string(),
+            experience: event.exp,
+            drop_items: event.drop,
+        };
+
+        let outcome = dispatch_event(&EntityDeath, self.tiers, event_data).await;
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.exp = modified.experience;
+            event.drop = modified.drop_items;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EventData {
+    entity: EntityData,
+    cause: String,
+    experience: u32,
+    drop_items: bool,
+}
+
+impl EventFields for EventData {
+    const MUTABLE_FIELDS: &'static [&'static str] = &["experience", "drop_items"];
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        if let Some(value) = self.entity.field(lua, "entity", key) {
+            return value;
+        }
+
+        match key {
+            "cause" => self.cause.as_str().into_lua(lua),
+            "experience" => self.experience.into_lua(lua),
+            "drop_items" => self.drop_items.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        match key {
+            "experience" => self.experience = lua.unpack(value)?,
+            "drop_items" => self.drop_items = lua.unpack(value)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+pub struct EntityDeath;
+
+#[async_trait]
+impl LuaEvent for EntityDeath {
+    fn name(&self) -> &'static str {
+        "entity_death"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(EntityDeathEventHandler { tiers }), priority, true)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
@@ -1,17 +1,500 @@
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mlua::{FromLuaMulti, Function, IntoLua, Lua, Table, Value};
 use pumpkin::plugin::Context;
+use tokio::sync::mpsc;
+
+use crate::lua::runtime::call_as_plugin;
+use crate::lua::worker::{LuaCommand, command_backlog, send_event_command};
+
+use self::priority::ListenerPriority;
 
 pub mod block_break;
 pub mod block_place;
+pub mod counts;
+pub mod custom;
+pub mod delivery;
+pub mod entity;
+pub mod entity_damage;
+pub mod entity_death;
+pub mod object;
 pub mod player_chat;
+pub mod player_death;
+pub mod player_interact;
+pub mod player_interact_entity;
 pub mod player_join;
 pub mod player_leave;
+pub mod player_move;
+pub mod player_teleport;
+pub mod priority;
+pub mod region;
+pub mod server;
+
+/// How long a blocking event handler waits for the Lua side to answer.
+const EVENT_RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Once this many commands are waiting for the Lua worker, fire-and-forget
+/// events are shed so the queue keeps room for events a handler waits on.
+const SHED_BACKLOG: usize = crate::lua::COMMAND_CHANNEL_CAPACITY / 2;
+
+/// The registry key of the table holding every event's listener table. It is
+/// kept out of the `pumpkin` API so plugins cannot reach each other's
+/// listeners.
+const LISTENERS_KEY: &str = "plua.listeners";
+
+/// The typed data of an event, shared with the Lua worker it is sent to.
+pub type EventPayload = Arc<dyn Any + Send + Sync>;
+
+/// The answer the Lua worker sends back after running the listeners for an event.
+#[derive(Clone, Default)]
+pub struct EventOutcome {
+    pub cancelled: bool,
+    /// The event data if a listener modified it, to be copied back into the Pumpkin event.
+    pub event_data: Option<EventPayload>,
+}
+
+impl EventOutcome {
+    /// The modified event data, if any listener changed it.
+    pub fn modified<T: Any>(&self) -> Option<&T> {
+        self.event_data.as_deref()?.downcast_ref()
+    }
+}
+
+/// Describes an event Lua plugins can listen to. Adding an event only requires
+/// implementing this trait and listing the descriptor in [`registry`].
+#[async_trait]
+pub trait LuaEvent: Send + Sync {
+    /// The name Lua listeners register with, e.g. `player_join`.
+    fn name(&self) -> &'static str;
+
+    /// Registers the Pumpkin event handlers that feed this event to Lua.
+    async fn register(&self, context: &Context) -> Result<(), String>;
+
+    /// Creates the listener table for this event.
+    fn setup_lua_event(&self, lua: &Lua, listeners: &Table) -> mlua::Result<()> {
+        listeners.set(self.name(), lua.create_table()?)
+    }
+
+    /// Whether the event has to be sent to Lua for the given priority tiers.
+    fn has_listeners(&self, tiers: &[ListenerPriority]) -> bool {
+        counts::any(self.name(), tiers)
+    }
+
+    /// Runs the listeners of the given priority tiers for one occurrence of the event.
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome>;
+}
+
+/// Every event PLua exposes to Lua.
+pub fn registry() -> &'static [&'static dyn LuaEvent] {
+    &[
+        &player_join::PlayerJoin,
+        &player_leave::PlayerLeave,
+        &player_chat::PlayerChat,
+        &block_place::BlockPlace,
+        &block_break::BlockBreak,
+        &entity_damage::EntityDamage,
+        &entity_death::EntityDeath,
+        &player_death::PlayerDeath,
+        &player_move::PlayerMove,
+        &player_teleport::PlayerTeleport,
+        &player_interact::PlayerInteract,
+        &player_interact_entity::PlayerInteractEntity,
+        &region::RegionEnter,
+        &region::RegionLeave,
+        &server::ServerStarted,
+        &server::ServerStopping,
+        &server::Tick,
+    ]
+}
+
+pub fn find(name: &str) -> Option<&'static dyn LuaEvent> {
+    registry()
+        .iter()
+        .copied()
+        .find(|event| event.name() == name)
+}
+
+/// Whether Lua listeners can be registered for an event, either built in or
+/// defined by a plugin.
+pub fn is_known(lua: &Lua, name: &str) -> bool {
+    find(name).is_some() || custom::is_defined(lua, name)
+}
+
+/// The names of all built-in and plugin-defined events.
+pub fn event_names(lua: &Lua) -> Vec<String> {
+    registry()
+        .iter()
+        .map(|event| event.name().to_string())
+        .chain(custom::defined_names(lua))
+        .collect()
+}
 
 pub async fn register_events(context: &Context) -> Result<(), String> {
-    player_join::register(context).await?;
-    player_leave::register(context).await?;
-    player_chat::register(context).await?;
-    block_place::register(context).await?;
-    block_break::register(context).await?;
+    for event in registry() {
+        event.register(context).await?;
+    }
 
     Ok(())
 }
+
+pub fn setup_lua_events(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
+    let listeners = lua.create_table()?;
+    for event in registry() {
+        event.setup_lua_event(lua, &listeners)?;
+    }
+    lua.set_named_registry_value(LISTENERS_KEY, listeners)?;
+
+    custom::setup_lua_api(lua, events_table)
+}
+
+/// Returns the listener table of a built-in or plugin-defined event.
+pub fn listener_table(lua: &Lua, event_type: &str) -> mlua::Result<Table> {
+    lua.named_registry_value::<Table>(LISTENERS_KEY)?
+        .get(event_type)
+}
+
+/// Creates the listener table of a plugin-defined event.
+fn add_listener_table(lua: &Lua, event_type: &str) -> mlua::Result<()> {
+    lua.named_registry_value::<Table>(LISTENERS_KEY)?
+        .set(event_type, lua.create_table()?)
+}
+
+/// Turns a Pumpkin enum variant such as `RightClickBlock` into the
+/// `right_click_block` form used in Lua event data.
+pub fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{:?}", value).chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+
+    name
+}
+
+/// Looks up an event and its listener table and runs the listeners.
+pub async fn trigger_event(
+    lua: &Lua,
+    event_type: &str,
+    tiers: &[ListenerPriority],
+    event_data: &EventPayload,
+) -> EventOutcome {
+    let Some(event) = find(event_type) else {
+        log::warn!("Unknown event type: {}", event_type);
+        return EventOutcome::default();
+    };
+
+    let listeners = match listener_table(lua, event_type) {
+        Ok(listeners) => listeners,
+        Err(_) => return EventOutcome::default(),
+    };
+
+    match event
+        .trigger_event(lua, &listeners, event_data, tiers)
+        .await
+    {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::error!("Error triggering {} event: {}", event_type, e);
+            EventOutcome::default()
+        }
+    }
+}
+
+/// Sends an event to the Lua worker and waits for the listeners of the given
+/// priority tiers to finish. Nothing is sent when no listener would run.
+pub async fn dispatch_event(
+    event: &dyn LuaEvent,
+    tiers: &'static [ListenerPriority],
+    event_data: impl Any + Send + Sync,
+) -> EventOutcome {
+    dispatch_event_within(event, tiers, event_data, EVENT_RESPONSE_TIMEOUT).await
+}
+
+/// Like [`dispatch_event`], but waits up to `timeout` for the listeners.
+pub async fn dispatch_event_within(
+    event: &dyn LuaEvent,
+    tiers: &'static [ListenerPriority],
+    event_data: impl Any + Send + Sync,
+    timeout: Duration,
+) -> EventOutcome {
+    if !event.has_listeners(tiers) {
+        return EventOutcome::default();
+    }
+
+    let event_type = event.name();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let receivers = match send_event_command(LuaCommand::TriggerEvent {
+        event_type: event_type.to_string(),
+        tiers,
+        event_data: Arc::new(event_data),
+        response: Some(tx),
+    }) {
+        Ok(receivers) => {
+            delivery::record_sent();
+            receivers
+        }
+        Err(e) => {
+            log::error!("Failed to send {} event to Lua: {}", event_type, e);
+            return EventOutcome::default();
+        }
+    };
+
+    let mut outcome = EventOutcome::default();
+    let collected = tokio::time::timeout(timeout, async {
+        for _ in 0..receivers {
+            match rx.recv().await {
+                Some(result) => {
+                    outcome.cancelled |= result.cancelled;
+                    if result.event_data.is_some() {
+                        outcome.event_data = result.event_data;
+                    }
+                }
+                None => break,
+            }
+        }
+    })
+    .await;
+
+    if collected.is_err() {
+        delivery::record_timeout();
+        log::warn!(
+            "Timed out waiting for Lua listeners of {} event, continuing without them",
+            event_type
+        );
+    }
+
+    outcome
+}
+
+/// Sends an event to the Lua worker without waiting for the listeners. The
+/// event is shed instead when the worker is falling behind.
+pub fn fire_event(
+    event: &dyn LuaEvent,
+    tiers: &'static [ListenerPriority],
+    event_data: impl Any + Send + Sync,
+) {
+    if !event.has_listeners(tiers) {
+        return;
+    }
+
+    if command_backlog() >= SHED_BACKLOG {
+        delivery::record_shed();
+        return;
+    }
+
+    match send_event_command(LuaCommand::TriggerEvent {
+        event_type: event.name().to_string(),
+        tiers,
+        event_data: Arc::new(event_data),
+        response: None,
+    }) {
+        Ok(_) => delivery::record_sent(),
+        Err(e) => log::error!("Failed to send {} event to Lua: {}", event.name(), e),
+    }
+}
+
+/// Adds the `cancelled` flag and `event:cancel()` method to an event table.
+pub fn make_cancellable(lua: &Lua, event_table: &Table) -> mlua::Result<()> {
+    event_table.set("cancelled", false)?;
+    event_table.set(
+        "cancel",
+        lua.create_function(|_, event: Table| event.set("cancelled", true))?,
+    )?;
+
+    Ok(())
+}
+
+/// A listener callback together with the data it was registered with.
+struct Listener {
+    priority: ListenerPriority,
+    order: u64,
+    owner: Option<String>,
+    callback: Function,
+}
+
+impl Listener {
+    /// Calls the listener in a coroutine owned by its plugin, so anything it
+    /// registers is attributed to that plugin.
+    async fn call<R: FromLuaMulti>(
+        &self,
+        lua: &Lua,
+        event: &(impl IntoLua + Clone),
+    ) -> mlua::Result<R> {
+        call_as_plugin(lua, self.owner.as_deref(), &self.callback, event.clone()).await
+    }
+}
+
+fn entry_priority(entry: &Table) -> ListenerPriority {
+    entry
+        .get::<String>("priority")
+        .ok()
+        .and_then(|name| ListenerPriority::from_name(&name))
+        .unwrap_or(ListenerPriority::Normal)
+}
+
+/// Returns the listeners registered for the given priority tiers, ordered by
+/// priority and then by registration order.
+fn collect_listeners(listeners: &Table, tiers: &[ListenerPriority]) -> Vec<Listener> {
+    let mut collected = Vec::new();
+
+    for (_, entry) in listeners.pairs::<Value, Table>().flatten() {
+        let Ok(callback) = entry.get::<Function>("callback") else {
+            continue;
+        };
+
+        let priority = entry_priority(&entry);
+
+        if tiers.contains(&priority) {
+            collected.push(Listener {
+                priority,
+                order: entry.get::<u64>("order").unwrap_or_default(),
+                owner: entry.get::<Option<String>>("owner").unwrap_or_default(),
+                callback,
+            });
+        }
+    }
+
+    collected.sort_by_key(|listener| (listener.priority, listener.order));
+    collected
+}
+
+/// Counts a newly registered listener entry so its event gets dispatched.
+pub fn count_listener(event_type: &str, entry: &Table) {
+    counts::add(event_type, entry_priority(entry));
+}
+
+/// Stops counting a listener entry that is being removed.
+pub fn forget_listener(event_type: &str, entry: &Table) {
+    counts::remove(event_type, entry_priority(entry));
+}
+
+/// Removes every listener owned by a plugin, returning how many were removed.
+pub fn remove_plugin_listeners(lua: &Lua, plugin_name: &str) -> mlua::Result<usize> {
+    let mut removed = 0;
+
+    for name in event_names(lua) {
+        let listeners = listener_table(lua, &name)?;
+
+        let owned: Vec<(Value, Table)> = listeners
+            .pairs::<Value, Table>()
+            .flatten()
+            .filter(|(_, entry)| {
+                entry
+                    .get::<Option<String>>("owner")
+                    .ok()
+                    .flatten()
+                    .is_some_and(|owner| owner == plugin_name)
+            })
+            .collect();
+
+        removed += owned.len();
+        for (id, entry) in owned {
+            forget_listener(&name, &entry);
+            listeners.set(id, Value::Nil)?;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Copies the event table so monitor listeners can observe it without being
+/// able to change the outcome.
+fn monitor_view(lua: &Lua, event_table: &Table) -> mlua::Result<Table> {
+    let view = lua.create_table()?;
+    for pair in event_table.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        view.set(key, value)?;
+    }
+
+    Ok(view)
+}
+
+/// Splits sorted listeners into the ones that may act on the event and the
+/// monitor listeners that run after them.
+fn split_monitors(listeners: &[Listener]) -> (&[Listener], &[Listener]) {
+    let split =
+        listeners.partition_point(|listener| listener.priority != ListenerPriority::Monitor);
+    listeners.split_at(split)
+}
+
+async fn call_monitors(
+    lua: &Lua,
+    event_type: &str,
+    monitors: &[Listener],
+    event_table: &Table,
+) -> mlua::Result<()> {
+    if monitors.is_empty() {
+        return Ok(());
+    }
+
+    let view = monitor_view(lua, event_table)?;
+    for listener in monitors {
+        if let Err(e) = listener.call::<()>(lua, &view).await {
+            log::error!("Error in {} event handler: {}", event_type, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls every listener of the given priority tiers with the event table.
+pub async fn call_listeners(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    event_table: &Table,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<()> {
+    let listeners = collect_listeners(listeners, tiers);
+    let (active, monitors) = split_monitors(&listeners);
+
+    for listener in active {
+        if let Err(e) = listener.call::<()>(lua, event_table).await {
+            log::error!("Error in {} event handler: {}", event_type, e);
+        }
+    }
+
+    call_monitors(lua, event_type, monitors, event_table).await
+}
+
+/// Calls every listener of the given priority tiers with the event table. A
+/// listener cancels the event by calling `event:cancel()` or by returning `false`.
+pub async fn call_cancellable_listeners(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    event_table: &Table,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<bool> {
+    let listeners = collect_listeners(listeners, tiers);
+    let (active, monitors) = split_monitors(&listeners);
+
+    for listener in active {
+        match listener.call::<Value>(lua, event_table).await {
+            Ok(Value::Boolean(false)) => event_table.set("cancelled", true)?,
+            Ok(_) => {}
+            Err(e) => log::error!("Error in {} event handler: {}", event_type, e),
+        }
+    }
+
+    let cancelled = event_table.get::<bool>("cancelled")?;
+    call_monitors(lua, event_type, monitors, event_table).await?;
+
+    Ok(cancelled)
+}
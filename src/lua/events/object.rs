@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, collect_listeners, priority::ListenerPriority, split_monitors,
+};
+
+/// Typed event data that Lua listeners see as a userdata object. Fields are
+/// only converted to Lua values when a listener reads them.
+pub trait EventFields: Send + Sync + Clone + 'static {
+    /// Whether listeners can cancel the event.
+    const CANCELLABLE: bool = false;
+
+    /// The fields listeners may change, copied back into the Pumpkin event.
+    const MUTABLE_FIELDS: &'static [&'static str] = &[];
+
+    /// Returns the value of a field, or `nil` if the event has no such field.
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value>;
+
+    /// Whether the event was already cancelled when it was sent to Lua, by an
+    /// earlier priority tier or another plugin.
+    fn cancelled(&self) -> bool {
+        false
+    }
+
+    /// Sets one of the [`Self::MUTABLE_FIELDS`].
+    fn set(&mut self, _lua: &Lua, _key: &str, _value: Value) -> mlua::Result<()> {
+        Ok(())
+    }
+}
+
+/// The userdata handed to listeners. The data is shared with the dispatching
+/// thread and only copied when a listener modifies it.
+struct EventObject<T> {
+    data: Arc<T>,
+    cancelled: bool,
+    modified: bool,
+    /// Set before monitor listeners run, as they may only observe the event.
+    frozen: bool,
+}
+
+impl<T: EventFields> EventObject<T> {
+    fn check_writable(&self, key: &str) -> mlua::Result<()> {
+        if self.frozen {
+            return Err(mlua::Error::RuntimeError(format!(
+                "Monitor listeners cannot modify the event ({})",
+                key
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) -> mlua::Result<()> {
+        if !T::CANCELLABLE {
+            return Err(mlua::Error::RuntimeError(
+                "This event cannot be cancelled".into(),
+            ));
+        }
+
+        self.check_writable("cancelled")?;
+
+        // Only cancelling is copied back into the Pumpkin event
+        if !cancelled && self.data.cancelled() {
+            return Err(mlua::Error::RuntimeError(
+                "This event was cancelled before it reached Lua and cannot be uncancelled".into(),
+            ));
+        }
+
+        self.cancelled = cancelled;
+        Ok(())
+    }
+}
+
+impl<T: EventFields> UserData for EventObject<T> {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("cancel", |_, this, ()| this.set_cancelled(true));
+
+        methods.add_meta_method(MetaMethod::Index, |lua, this, key: String| {
+            if T::CANCELLABLE && key == "cancelled" {
+                return Ok(Value::Boolean(this.cancelled));
+            }
+
+            this.data.get(lua, &key)
+        });
+
+        methods.add_meta_method_mut(
+            MetaMethod::NewIndex,
+            |lua, this, (key, value): (String, Value)| {
+                if key == "cancelled" {
+                    return this.set_cancelled(lua.unpack(value)?);
+                }
+
+                if !T::MUTABLE_FIELDS.contains(&key.as_str()) {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Event field {} is read-only",
+                        key
+                    )));
+                }
+
+                this.check_writable(&key)?;
+                Arc::make_mut(&mut this.data).set(lua, &key, value)?;
+                this.modified = true;
+                Ok(())
+            },
+        );
+    }
+}
+
+/// What the listeners did with an event.
+pub struct ListenerResult<T> {
+    pub cancelled: bool,
+    pub data: Arc<T>,
+    pub modified: bool,
+}
+
+impl<T: EventFields> ListenerResult<T> {
+    pub fn into_outcome(self) -> EventOutcome {
+        EventOutcome {
+            cancelled: self.cancelled,
+            event_data: self.modified.then(|| self.data as EventPayload),
+        }
+    }
+}
+
+/// Calls every listener of the given priority tiers with the event data. For
+/// cancellable events a listener cancels the event by calling `event:cancel()`,
+/// setting `event.cancelled` or returning `false`.
+pub async fn call_typed_listeners<T: EventFields>(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    data: Arc<T>,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<ListenerResult<T>> {
+    let listeners = collect_listeners(listeners, tiers);
+    let cancelled = T::CANCELLABLE && data.cancelled();
+    if listeners.is_empty() {
+        return Ok(ListenerResult {
+            cancelled,
+            data,
+            modified: false,
+        });
+    }
+
+    let (active, monitors) = split_monitors(&listeners);
+    let event = lua.create_userdata(EventObject {
+        data,
+        cancelled,
+        modified: false,
+        frozen: false,
+    })?;
+
+    for listener in active {
+        match listener.call::<Value>(lua, &event).await {
+            Ok(Value::Boolean(false)) if T::CANCELLABLE => {
+                event.borrow_mut::<EventObject<T>>()?.cancelled = true;
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("Error in {} event handler: {}", event_type, e),
+        }
+    }
+
+    event.borrow_mut::<EventObject<T>>()?.frozen = true;
+    for listener in monitors {
+        if let Err(e) = listener.call::<()>(lua, &event).await {
+            log::error!("Error in {} event handler: {}", event_type, e);
+        }
+    }
+
+    let object = event.take::<EventObject<T>>()?;
+    Ok(ListenerResult {
+        cancelled: object.cancelled,
+        data: object.data,
+        modified: object.modified,
+    })
+}
+
+/// Recovers the typed data of an event sent to the Lua worker.
+pub fn downcast_payload<T: EventFields>(
+    event_type: &str,
+    payload: &EventPayload,
+) -> Option<Arc<T>> {
+    match payload.clone().downcast::<T>() {
+        Ok(data) => Some(data),
+        Err(_) => {
+            log::error!("Unexpected data for {} event", event_type);
+            None
+        }
+    }
+}
+
+/// Runs the listeners of an event whose payload is a `T`.
+pub async fn trigger_typed_event<T: EventFields>(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    payload: &EventPayload,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<EventOutcome> {
+    let Some(data) = downcast_payload::<T>(event_type, payload) else {
+        return Ok(EventOutcome::default());
+    };
+
+    let result = call_typed_listeners(lua, event_type, listeners, data, tiers).await?;
+    Ok(result.into_outcome())
+}
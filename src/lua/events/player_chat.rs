@@ -1,16 +1,22 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{IntoLua, Lua, Table, Value};
 use pumpkin::{
-    plugin::{Context, EventHandler, EventPriority, player::player_chat::PlayerChatEvent},
+    plugin::{Cancellable, Context, EventHandler, player::player_chat::PlayerChatEvent},
     server::Server,
 };
 use pumpkin_api_macros::with_runtime;
 
-use crate::lua::worker::{LuaCommand, send_event_command};
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
 
-pub struct PlayerChatEventHandler;
+pub struct PlayerChatEventHandler {
+    tiers: &'static [ListenerPriority],
+}
 
 #[with_runtime(global)]
 #[async_trait]
@@ -21,81 +27,82 @@ impl EventHandler<PlayerChatEvent> for PlayerChatEventHandler {
             player_uuid: event.player.gameprofile.id.to_string(),
             message: event.message.clone(),
             recipients: event.recipients.len(),
+            cancelled: event.cancelled(),
         };
 
-        if let Err(e) = send_event_command(LuaCommand::TriggerEvent {
-            event_type: "player_chat".to_string(),
-            event_data: serde_json::to_string(&event_data).unwrap_or_default(),
-        }) {
-            log::error!("Failed to send player chat event to Lua: {}", e);
+        let outcome = dispatch_event(&PlayerChat, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.message = modified.message.clone();
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
 struct EventData {
     player_name: String,
     player_uuid: String,
     message: String,
     recipients: usize,
+    cancelled: bool,
 }
 
-pub async fn register(context: &Context) -> Result<(), String> {
-    context
-        .register_event(
-            Arc::new(PlayerChatEventHandler),
-            EventPriority::Lowest,
-            true,
-        )
-        .await;
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+    const MUTABLE_FIELDS: &'static [&'static str] = &["message"];
 
-    Ok(())
-}
-
-pub fn setup_lua_event(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
-    let player_chat_listeners = lua.create_table()?;
-    events_table.set("player_chat", player_chat_listeners)?;
-
-    Ok(())
-}
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
 
-pub fn trigger_event(lua: &Lua, event_data_json: &str) -> mlua::Result<()> {
-    let event_data: EventData = match serde_json::from_str(event_data_json) {
-        Ok(data) => data,
-        Err(e) => {
-            log::error!("Failed to parse player chat event data: {}", e);
-            return Ok(());
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "message" => self.message.as_str().into_lua(lua),
+            "recipients" => self.recipients.into_lua(lua),
+            _ => Ok(Value::Nil),
         }
-    };
-
-    let globals = lua.globals();
-    let pumpkin: Table = globals.get("pumpkin")?;
+    }
 
-    let events: Table = match pumpkin.get("events") {
-        Ok(events) => events,
-        Err(_) => {
-            return Ok(());
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        if key == "message" {
+            self.message = lua.unpack(value)?;
         }
-    };
 
-    let player_chat_listeners: Table = match events.get("player_chat") {
-        Ok(listeners) => listeners,
-        Err(_) => {
-            return Ok(());
-        }
-    };
+        Ok(())
+    }
+}
 
-    let event_table = lua.create_table()?;
-    event_table.set("player_name", event_data.player_name)?;
-    event_table.set("player_uuid", event_data.player_uuid)?;
-    event_table.set("message", event_data.message)?;
-    event_table.set("recipients", event_data.recipients)?;
+pub struct PlayerChat;
 
-    for (_, callback) in player_chat_listeners.pairs::<Value, Function>().flatten() {
-        if let Err(e) = callback.call::<()>(event_table.clone()) {
-            log::error!("Error in player_chat event handler: {}", e);
+#[async_trait]
+impl LuaEvent for PlayerChat {
+    fn name(&self) -> &'static str {
+        "player_chat"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(PlayerChatEventHandler { tiers }), priority, true)
+                .await;
         }
+
+        Ok(())
     }
 
-    Ok(())
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
 }
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    plugin::{Context, EventHandler, player::player_death::PlayerDeathEvent},
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+use pumpkin_util::text::TextComponent;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
+
+pub struct PlayerDeathEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<PlayerDeathEvent> for PlayerDeathEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut PlayerDeathEvent) {
+        let event_data = EventData {
+            player_name: event.player.gameprofile.name.clone(),
+            player_uuid: event.player.gameprofile.id.to_string(),
+            cause: event.damage_type.message_id.to_string(),
+            death_message: event.death_message.clone().get_text(),
+            experience: event.exp,
+            drop_items: event.drop,
+        };
+
+        let outcome = dispatch_event(&PlayerDeath, self.tiers, event_data).await;
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            if modified.death_message != event.death_message.clone().get_text() {
+                event.death_message = TextComponent::text(modified.death_message.clone());
+            }
+            event.exp = modified.experience;
+            event.drop = modified.drop_items;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EventData {
+    player_name: String,
+    player_uuid: String,
+    cause: String,
+    death_message: String,
+    experience: u32,
+    drop_items: bool,
+}
+
+impl EventFields for EventData {
+    const MUTABLE_FIELDS: &'static [&'static str] = &["death_message", "experience", "drop_items"];
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "cause" => self.cause.as_str().into_lua(lua),
+            "death_message" => self.death_message.as_str().into_lua(lua),
+            "experience" => self.experience.into_lua(lua),
+            "drop_items" => self.drop_items.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        match key {
+            "death_message" => self.death_message = lua.unpack(value)?,
+            "experience" => self.experience = lua.unpack(value)?,
+            "drop_items" => self.drop_items = lua.unpack(value)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+pub struct PlayerDeath;
+
+#[async_trait]
+impl LuaEvent for PlayerDeath {
+    fn name(&self) -> &'static str {
+        "player_death"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(PlayerDeathEventHandler { tiers }), priority, true)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    plugin::{
+        Cancellable, Context, EventHandler, player::player_interact_event::PlayerInteractEvent,
+    },
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+    variant_name,
+};
+
+pub struct PlayerInteractEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<PlayerInteractEvent> for PlayerInteractEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut PlayerInteractEvent) {
+        let event_data = EventData {
+            player_name: event.player.gameprofile.name.clone(),
+            player_uuid: event.player.gameprofile.id.to_string(),
+            action: variant_name(&event.action),
+            hand: variant_name(&event.hand),
+            item: event.item.lock().await.item.registry_key.to_string(),
+            block_type: event.block.name.to_string(),
+            position: event.clicked_pos.map(|pos| (pos.0.x, pos.0.y, pos.0.z)),
+            face: event.face.as_ref().map(variant_name),
+            cancelled: event.cancelled(),
+        };
+
+        let outcome = dispatch_event(&PlayerInteract, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EventData {
+    player_name: String,
+    player_uuid: String,
+    action: String,
+    hand: String,
+    item: String,
+    block_type: String,
+    position: Option<(i32, i32, i32)>,
+    face: Option<String>,
+    cancelled: bool,
+}
+
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "action" => self.action.as_str().into_lua(lua),
+            "hand" => self.hand.as_str().into_lua(lua),
+            "item" => self.item.as_str().into_lua(lua),
+            "block_type" => self.block_type.as_str().into_lua(lua),
+            "position_x" => self.position.map(|(x, _, _)| x).into_lua(lua),
+            "position_y" => self.position.map(|(_, y, _)| y).into_lua(lua),
+            "position_z" => self.position.map(|(_, _, z)| z).into_lua(lua),
+            "face" => self.face.as_deref().into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+}
+
+pub struct PlayerInteract;
+
+#[async_trait]
+impl LuaEvent for PlayerInteract {
+    fn name(&self) -> &'static str {
+        "player_interact"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(
+                    Arc::new(PlayerInteractEventHandler { tiers }),
+                    priority,
+                    true,
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    plugin::{
+        Cancellable, Context, EventHandler,
+        player::player_interact_entity_event::PlayerInteractEntityEvent,
+    },
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    entity::EntityData,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+    variant_name,
+};
+
+pub struct PlayerInteractEntityEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<PlayerInteractEntityEvent> for PlayerInteractEntityEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut PlayerInteractEntityEvent) {
+        let event_data = EventData {
+            player_name: event.player.gameprofile.name.clone(),
+            player_uuid: event.player.gameprofile.id.to_string(),
+            entity: EntityData::from_entity(event.entity.as_ref()),
+            action: variant_name(&event.action),
+            hand: variant_name(&event.hand),
+            item: event.item.lock().await.item.registry_key.to_string(),
+            cancelled: event.cancelled(),
+        };
+
+        let outcome = dispatch_event(&PlayerInteractEntity, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EventData {
+    player_name: String,
+    player_uuid: String,
+    entity: EntityData,
+    action: String,
+    hand: String,
+    item: String,
+    cancelled: bool,
+}
+
+impl EventFields for EventData {
+    const CANCELLABLE: bool = true;
+
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        if let Some(value) = self.entity.field(lua, "entity", key) {
+            return value;
+        }
+
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "action" => self.action.as_str().into_lua(lua),
+            "hand" => self.hand.as_str().into_lua(lua),
+            "item" => self.item.as_str().into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+}
+
+pub struct PlayerInteractEntity;
+
+#[async_trait]
+impl LuaEvent for PlayerInteractEntity {
+    fn name(&self) -> &'static str {
+        "player_interact_entity"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(
+                    Arc::new(PlayerInteractEntityEventHandler { tiers }),
+                    priority,
+                    true,
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
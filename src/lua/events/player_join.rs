@@ -1,16 +1,23 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{IntoLua, Lua, Table, Value};
 use pumpkin::{
-    plugin::{Context, EventHandler, EventPriority, player::player_join::PlayerJoinEvent},
+    plugin::{Context, EventHandler, player::player_join::PlayerJoinEvent},
     server::Server,
 };
 use pumpkin_api_macros::with_runtime;
+use pumpkin_util::text::TextComponent;
 
-use crate::lua::worker::{LuaCommand, send_event_command};
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
 
-pub struct PlayerJoinEventHandler;
+pub struct PlayerJoinEventHandler {
+    tiers: &'static [ListenerPriority],
+}
 
 #[with_runtime(global)]
 #[async_trait]
@@ -22,77 +29,67 @@ impl EventHandler<PlayerJoinEvent> for PlayerJoinEventHandler {
             join_message: event.join_message.clone().get_text(),
         };
 
-        if let Err(e) = send_event_command(LuaCommand::TriggerEvent {
-            event_type: "player_join".to_string(),
-            event_data: serde_json::to_string(&event_data).unwrap_or_default(),
-        }) {
-            log::error!("Failed to send player join event to Lua: {}", e);
+        let outcome = dispatch_event(&PlayerJoin, self.tiers, event_data).await;
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.join_message = TextComponent::text(modified.join_message.clone());
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
 struct EventData {
     player_name: String,
     player_uuid: String,
     join_message: String,
 }
 
-pub async fn register(context: &Context) -> Result<(), String> {
-    context
-        .register_event(
-            Arc::new(PlayerJoinEventHandler),
-            EventPriority::Lowest,
-            true,
-        )
-        .await;
+impl EventFields for EventData {
+    const MUTABLE_FIELDS: &'static [&'static str] = &["join_message"];
 
-    Ok(())
-}
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "join_message" => self.join_message.as_str().into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
 
-pub fn setup_lua_event(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
-    let player_join_listeners = lua.create_table()?;
-    events_table.set("player_join", player_join_listeners)?;
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        if key == "join_message" {
+            self.join_message = lua.unpack(value)?;
+        }
 
-    Ok(())
+        Ok(())
+    }
 }
 
-pub fn trigger_event(lua: &Lua, event_data_json: &str) -> mlua::Result<()> {
-    let event_data: EventData = match serde_json::from_str(event_data_json) {
-        Ok(data) => data,
-        Err(e) => {
-            log::error!("Failed to parse player join event data: {}", e);
-            return Ok(());
-        }
-    };
-
-    let globals = lua.globals();
-    let pumpkin: Table = globals.get("pumpkin")?;
+pub struct PlayerJoin;
 
-    let events: Table = match pumpkin.get("events") {
-        Ok(events) => events,
-        Err(_) => {
-            return Ok(());
-        }
-    };
+#[async_trait]
+impl LuaEvent for PlayerJoin {
+    fn name(&self) -> &'static str {
+        "player_join"
+    }
 
-    let player_join_listeners: Table = match events.get("player_join") {
-        Ok(listeners) => listeners,
-        Err(_) => {
-            return Ok(());
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(PlayerJoinEventHandler { tiers }), priority, true)
+                .await;
         }
-    };
-
-    let event_table = lua.create_table()?;
-    event_table.set("player_name", event_data.player_name)?;
-    event_table.set("player_uuid", event_data.player_uuid)?;
-    event_table.set("join_message", event_data.join_message)?;
 
-    for (_, callback) in player_join_listeners.pairs::<Value, Function>().flatten() {
-        if let Err(e) = callback.call::<()>(event_table.clone()) {
-            log::error!("Error in player_join event handler: {}", e);
-        }
+        Ok(())
     }
 
-    Ok(())
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
 }
@@ -1,17 +1,23 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{IntoLua, Lua, Table, Value};
 use pumpkin::{
-    plugin::{Context, EventHandler, EventPriority, player::player_leave::PlayerLeaveEvent},
+    plugin::{Context, EventHandler, player::player_leave::PlayerLeaveEvent},
     server::Server,
 };
 use pumpkin_api_macros::with_runtime;
+use pumpkin_util::text::TextComponent;
 
-use crate::lua::worker::LuaCommand;
-use crate::lua::worker::send_event_command;
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    object::{EventFields, trigger_typed_event},
+    priority::{ListenerPriority, priority_tiers},
+};
 
-pub struct PlayerLeaveEventHandler;
+pub struct PlayerLeaveEventHandler {
+    tiers: &'static [ListenerPriority],
+}
 
 #[with_runtime(global)]
 #[async_trait]
@@ -23,77 +29,67 @@ impl EventHandler<PlayerLeaveEvent> for PlayerLeaveEventHandler {
             leave_message: event.leave_message.clone().get_text(),
         };
 
-        if let Err(e) = send_event_command(LuaCommand::TriggerEvent {
-            event_type: "player_leave".to_string(),
-            event_data: serde_json::to_string(&event_data).unwrap_or_default(),
-        }) {
-            log::error!("Failed to send player leave event to Lua: {}", e);
+        let outcome = dispatch_event(&PlayerLeave, self.tiers, event_data).await;
+
+        if let Some(modified) = outcome.modified::<EventData>() {
+            event.leave_message = TextComponent::text(modified.leave_message.clone());
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
 struct EventData {
     player_name: String,
     player_uuid: String,
     leave_message: String,
 }
 
-pub async fn register(context: &Context) -> Result<(), String> {
-    context
-        .register_event(
-            Arc::new(PlayerLeaveEventHandler),
-            EventPriority::Lowest,
-            true,
-        )
-        .await;
+impl EventFields for EventData {
+    const MUTABLE_FIELDS: &'static [&'static str] = &["leave_message"];
 
-    Ok(())
-}
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "leave_message" => self.leave_message.as_str().into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
 
-pub fn setup_lua_event(lua: &Lua, events_table: &Table) -> mlua::Result<()> {
-    let player_leave_listeners = lua.create_table()?;
-    events_table.set("player_leave", player_leave_listeners)?;
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        if key == "leave_message" {
+            self.leave_message = lua.unpack(value)?;
+        }
 
-    Ok(())
+        Ok(())
+    }
 }
 
-pub fn trigger_event(lua: &Lua, event_data_json: &str) -> mlua::Result<()> {
-    let event_data: EventData = match serde_json::from_str(event_data_json) {
-        Ok(data) => data,
-        Err(e) => {
-            log::error!("Failed to parse player leave event data: {}", e);
-            return Ok(());
-        }
-    };
-
-    let globals = lua.globals();
-    let pumpkin: Table = globals.get("pumpkin")?;
+pub struct PlayerLeave;
 
-    let events: Table = match pumpkin.get("events") {
-        Ok(events) => events,
-        Err(_) => {
-            return Ok(());
-        }
-    };
+#[async_trait]
+impl LuaEvent for PlayerLeave {
+    fn name(&self) -> &'static str {
+        "player_leave"
+    }
 
-    let player_leave_listeners: Table = match events.get("player_leave") {
-        Ok(listeners) => listeners,
-        Err(_) => {
-            return Ok(());
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(PlayerLeaveEventHandler { tiers }), priority, true)
+                .await;
         }
-    };
-
-    let event_table = lua.create_table()?;
-    event_table.set("player_name", event_data.player_name)?;
-    event_table.set("player_uuid", event_data.player_uuid)?;
-    event_table.set("leave_message", event_data.leave_message)?;
 
-    for (_, callback) in player_leave_listeners.pairs::<Value, Function>().flatten() {
-        if let Err(e) = callback.call::<()>(event_table.clone()) {
-            log::error!("Error in player_leave event handler: {}", e);
-        }
+        Ok(())
     }
 
-    Ok(())
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<EventData>(lua, self.name(), listeners, event_data, tiers).await
+    }
 }
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::{
+    entity::player::Player,
+    plugin::{Cancellable, Context, EventHandler, player::player_move::PlayerMoveEvent},
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+use pumpkin_data::dimension::VanillaDimensionType;
+use pumpkin_util::math::vector3::Vector3;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, counts, dispatch_event,
+    object::{EventFields, call_typed_listeners, downcast_payload},
+    priority::{ListenerPriority, priority_tiers},
+    region,
+};
+
+pub struct PlayerMoveEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<PlayerMoveEvent> for PlayerMoveEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut PlayerMoveEvent) {
+        let event_data = MoveData::new(&event.player, event.from, event.to, event.cancelled());
+
+        let outcome = dispatch_event(&PlayerMove, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<MoveData>() {
+            event.to = modified.to();
+        }
+    }
+}
+
+/// Event data shared by the movement events.
+#[derive(Clone)]
+pub(super) struct MoveData {
+    player_name: String,
+    player_uuid: String,
+    world: &'static str,
+    from_x: f64,
+    from_y: f64,
+    from_z: f64,
+    to_x: f64,
+    to_y: f64,
+    to_z: f64,
+    cancelled: bool,
+}
+
+impl MoveData {
+    pub(super) fn new(
+        player: &Player,
+        from: Vector3<f64>,
+        to: Vector3<f64>,
+        cancelled: bool,
+    ) -> Self {
+        Self {
+            player_name: player.gameprofile.name.clone(),
+            player_uuid: player.gameprofile.id.to_string(),
+            world: world_name(player),
+            from_x: from.x,
+            from_y: from.y,
+            from_z: from.z,
+            to_x: to.x,
+            to_y: to.y,
+            to_z: to.z,
+            cancelled,
+        }
+    }
+
+    pub(super) fn to(&self) -> Vector3<f64> {
+        Vector3::new(self.to_x, self.to_y, self.to_z)
+    }
+}
+
+/// The name Lua uses for the world a player is in.
+fn world_name(player: &Player) -> &'static str {
+    match player.world().dimension_type {
+        VanillaDimensionType::Overworld => "overworld",
+        VanillaDimensionType::OverworldCaves => "overworld_caves",
+        VanillaDimensionType::TheNether => "the_nether",
+        VanillaDimensionType::TheEnd => "the_end",
+    }
+}
+
+impl EventFields for MoveData {
+    const CANCELLABLE: bool = true;
+    const MUTABLE_FIELDS: &'static [&'static str] = &["to_x", "to_y", "to_z"];
+
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "world" => self.world.into_lua(lua),
+            "from_x" => self.from_x.into_lua(lua),
+            "from_y" => self.from_y.into_lua(lua),
+            "from_z" => self.from_z.into_lua(lua),
+            "to_x" => self.to_x.into_lua(lua),
+            "to_y" => self.to_y.into_lua(lua),
+            "to_z" => self.to_z.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    fn set(&mut self, lua: &Lua, key: &str, value: Value) -> mlua::Result<()> {
+        match key {
+            "to_x" => self.to_x = lua.unpack(value)?,
+            "to_y" => self.to_y = lua.unpack(value)?,
+            "to_z" => self.to_z = lua.unpack(value)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a movement event has to be sent to Lua: besides its own listeners,
+/// the final priority tier also drives the region enter and leave events.
+pub(super) fn move_has_listeners(event_type: &str, tiers: &[ListenerPriority]) -> bool {
+    counts::any(event_type, tiers)
+        || (tiers.contains(&ListenerPriority::Monitor) && region::has_region_listeners())
+}
+
+/// Runs the listeners of a movement event. Listeners can cancel the movement or
+/// redirect it by changing `to_x`, `to_y` and `to_z`. In the final priority
+/// tier, region enter and leave callbacks fire after the other listeners, if
+/// the movement was not cancelled, and may still cancel it before the monitor
+/// listeners see the outcome.
+pub(super) async fn trigger_move_event(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    event_data: &EventPayload,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<EventOutcome> {
+    let Some(event_data) = downcast_payload::<MoveData>(event_type, event_data) else {
+        return Ok(EventOutcome::default());
+    };
+
+    let (monitors, active): (Vec<ListenerPriority>, Vec<ListenerPriority>) = tiers
+        .iter()
+        .copied()
+        .partition(|priority| *priority == ListenerPriority::Monitor);
+
+    let mut result = call_typed_listeners(lua, event_type, listeners, event_data, &active).await?;
+    if monitors.is_empty() {
+        return Ok(result.into_outcome());
+    }
+
+    if !result.cancelled {
+        let data = &result.data;
+        result.cancelled = region::check_regions(
+            lua,
+            &data.player_name,
+            &data.player_uuid,
+            data.world,
+            (data.from_x, data.from_y, data.from_z),
+            (data.to_x, data.to_y, data.to_z),
+        )
+        .await?;
+    }
+
+    // Monitor listeners see the outcome of every other listener and region
+    // callback
+    let mut data = result.data.clone();
+    Arc::make_mut(&mut data).cancelled = result.cancelled;
+    call_typed_listeners(lua, event_type, listeners, data, &monitors).await?;
+
+    Ok(result.into_outcome())
+}
+
+pub struct PlayerMove;
+
+#[async_trait]
+impl LuaEvent for PlayerMove {
+    fn name(&self) -> &'static str {
+        "player_move"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(Arc::new(PlayerMoveEventHandler { tiers }), priority, true)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    fn has_listeners(&self, tiers: &[ListenerPriority]) -> bool {
+        move_has_listeners(self.name(), tiers)
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_move_event(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
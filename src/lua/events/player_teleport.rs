@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{Lua, Table};
+use pumpkin::{
+    plugin::{Cancellable, Context, EventHandler, player::player_teleport::PlayerTeleportEvent},
+    server::Server,
+};
+use pumpkin_api_macros::with_runtime;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, dispatch_event,
+    player_move::{MoveData, move_has_listeners, trigger_move_event},
+    priority::{ListenerPriority, priority_tiers},
+};
+
+pub struct PlayerTeleportEventHandler {
+    tiers: &'static [ListenerPriority],
+}
+
+#[with_runtime(global)]
+#[async_trait]
+impl EventHandler<PlayerTeleportEvent> for PlayerTeleportEventHandler {
+    async fn handle_blocking(&self, _server: &Arc<Server>, event: &mut PlayerTeleportEvent) {
+        let event_data = MoveData::new(&event.player, event.from, event.to, event.cancelled());
+
+        let outcome = dispatch_event(&PlayerTeleport, self.tiers, event_data).await;
+
+        if outcome.cancelled {
+            event.set_cancelled(true);
+        }
+
+        if let Some(modified) = outcome.modified::<MoveData>() {
+            event.to = modified.to();
+        }
+    }
+}
+
+pub struct PlayerTeleport;
+
+#[async_trait]
+impl LuaEvent for PlayerTeleport {
+    fn name(&self) -> &'static str {
+        "player_teleport"
+    }
+
+    async fn register(&self, context: &Context) -> Result<(), String> {
+        for (priority, tiers) in priority_tiers() {
+            context
+                .register_event(
+                    Arc::new(PlayerTeleportEventHandler { tiers }),
+                    priority,
+                    true,
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    fn has_listeners(&self, tiers: &[ListenerPriority]) -> bool {
+        move_has_listeners(self.name(), tiers)
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_move_event(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
@@ -0,0 +1,69 @@
+use pumpkin::plugin::EventPriority;
+
+/// The priority a Lua listener is registered with. Listeners run from `Lowest`
+/// to `Highest`, so higher priorities get the final say on an event. `Monitor`
+/// listeners run last and only observe the outcome; they cannot cancel or
+/// modify the event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ListenerPriority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    Highest,
+    Monitor,
+}
+
+impl ListenerPriority {
+    /// Every priority, in the order listeners run.
+    pub const ALL: [ListenerPriority; 6] = [
+        Self::Lowest,
+        Self::Low,
+        Self::Normal,
+        Self::High,
+        Self::Highest,
+        Self::Monitor,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lowest" => Some(Self::Lowest),
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            "highest" => Some(Self::Highest),
+            "monitor" => Some(Self::Monitor),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lowest => "lowest",
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Highest => "highest",
+            Self::Monitor => "monitor",
+        }
+    }
+}
+
+/// The Pumpkin handler priorities PLua registers for every event, each paired
+/// with the Lua priorities it dispatches.
+///
+/// Pumpkin runs `EventPriority::Highest` handlers first and `Lowest` last, the
+/// reverse of the Lua naming, so the Lua tiers are mapped in reverse order and
+/// `Monitor` shares the last Pumpkin tier, running after everything else.
+pub fn priority_tiers() -> [(EventPriority, &'static [ListenerPriority]); 5] {
+    [
+        (EventPriority::Highest, &[ListenerPriority::Lowest]),
+        (EventPriority::High, &[ListenerPriority::Low]),
+        (EventPriority::Normal, &[ListenerPriority::Normal]),
+        (EventPriority::Low, &[ListenerPriority::High]),
+        (
+            EventPriority::Lowest,
+            &[ListenerPriority::Highest, ListenerPriority::Monitor],
+        ),
+    ]
+}
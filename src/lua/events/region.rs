@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::plugin::Context;
+
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent,
+    object::{EventFields, trigger_typed_event},
+    priority::ListenerPriority,
+    trigger_event,
+};
+use crate::lua::runtime::active_plugin;
+
+type Position = (f64, f64, f64);
+
+/// The world a region is in when its bounds do not name one.
+const DEFAULT_WORLD: &str = "overworld";
+
+/// An axis-aligned box in a world, defined by a plugin through
+/// `pumpkin.regions.define`.
+struct Region {
+    owner: Option<String>,
+    world: String,
+    min: Position,
+    max: Position,
+}
+
+impl Region {
+    fn contains(&self, (x, y, z): Position) -> bool {
+        (self.min.0..=self.max.0).contains(&x)
+            && (self.min.1..=self.max.1).contains(&y)
+            && (self.min.2..=self.max.2).contains(&z)
+    }
+}
+
+/// The regions defined in a Lua state, stored as app data.
+#[derive(Default)]
+struct Regions(HashMap<String, Region>);
+
+fn read_position(bounds: &Table, key: &str) -> mlua::Result<Position> {
+    let corner: Table = bounds.get(key)?;
+    Ok((corner.get("x")?, corner.get("y")?, corner.get("z")?))
+}
+
+/// Creates the `pumpkin.regions` table.
+pub fn setup_lua_api(lua: &Lua, pumpkin_table: &Table) -> mlua::Result<()> {
+    lua.set_app_data(Regions::default());
+
+    let regions_table = lua.create_table()?;
+
+    regions_table.set(
+        "define",
+        lua.create_function(|lua_ctx, (name, bounds): (String, Table)| {
+            let a = read_position(&bounds, "min")?;
+            let b = read_position(&bounds, "max")?;
+            let world: Option<String> = bounds.get("world")?;
+
+            let region = Region {
+                owner: active_plugin(lua_ctx),
+                world: world.unwrap_or_else(|| DEFAULT_WORLD.to_string()),
+                min: (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+                max: (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+            };
+
+            let mut regions = lua_ctx
+                .app_data_mut::<Regions>()
+                .ok_or_else(|| mlua::Error::RuntimeError("Regions not initialized".into()))?;
+
+            if regions
+                .0
+                .get(&name)
+                .is_some_and(|existing| existing.owner != region.owner)
+            {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Region {} is already defined by another plugin",
+                    name
+                )));
+            }
+
+            regions.0.insert(name, region);
+            Ok(())
+        })?,
+    )?;
+
+    regions_table.set(
+        "remove",
+        lua.create_function(|lua_ctx, name: String| {
+            let owner = active_plugin(lua_ctx);
+            let Some(mut regions) = lua_ctx.app_data_mut::<Regions>() else {
+                return Ok(false);
+            };
+
+            // Plugins can only remove the regions they defined themselves
+            if regions
+                .0
+                .get(&name)
+                .is_none_or(|region| region.owner != owner)
+            {
+                return Ok(false);
+            }
+
+            Ok(regions.0.remove(&name).is_some())
+        })?,
+    )?;
+
+    regions_table.set(
+        "contains",
+        lua.create_function(
+            |lua_ctx, (name, x, y, z, world): (String, f64, f64, f64, Option<String>)| {
+                Ok(lua_ctx.app_data_ref::<Regions>().is_some_and(|regions| {
+                    regions.0.get(&name).is_some_and(|region| {
+                        world.as_ref().is_none_or(|world| *world == region.world)
+                            && region.contains((x, y, z))
+                    })
+                }))
+            },
+        )?,
+    )?;
+
+    regions_table.set(
+        "list",
+        lua.create_function(|lua_ctx, ()| {
+            let mut names: Vec<String> = lua_ctx
+                .app_data_ref::<Regions>()
+                .map(|regions| regions.0.keys().cloned().collect())
+                .unwrap_or_default();
+            names.sort();
+            Ok(names)
+        })?,
+    )?;
+
+    pumpkin_table.set("regions", regions_table)?;
+
+    Ok(())
+}
+
+/// Removes every region defined by a plugin, returning how many were removed.
+pub fn remove_plugin_regions(lua: &Lua, plugin_name: &str) -> usize {
+    let Some(mut regions) = lua.app_data_mut::<Regions>() else {
+        return 0;
+    };
+
+    let before = regions.0.len();
+    regions
+        .0
+        .retain(|_, region| region.owner.as_deref() != Some(plugin_name));
+    before - regions.0.len()
+}
+
+#[derive(Clone)]
+struct RegionData {
+    player_name: String,
+    player_uuid: String,
+    region: String,
+    world: String,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl EventFields for RegionData {
+    const CANCELLABLE: bool = true;
+
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "player_name" => self.player_name.as_str().into_lua(lua),
+            "player_uuid" => self.player_uuid.as_str().into_lua(lua),
+            "region" => self.region.as_str().into_lua(lua),
+            "world" => self.world.as_str().into_lua(lua),
+            "x" => self.x.into_lua(lua),
+            "y" => self.y.into_lua(lua),
+            "z" => self.z.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+}
+
+/// Whether any listener is registered for region enter or leave events.
+pub fn has_region_listeners() -> bool {
+    RegionEnter.has_listeners(&ListenerPriority::ALL)
+        || RegionLeave.has_listeners(&ListenerPriority::ALL)
+}
+
+/// Fires `region_leave` and `region_enter` for every region in the player's
+/// world that a movement crosses the boundary of. Returns whether a region
+/// listener cancelled the movement.
+pub async fn check_regions(
+    lua: &Lua,
+    player_name: &str,
+    player_uuid: &str,
+    world: &str,
+    from: Position,
+    to: Position,
+) -> mlua::Result<bool> {
+    let mut transitions: Vec<(&'static str, String)> = {
+        let Some(regions) = lua.app_data_ref::<Regions>() else {
+            return Ok(false);
+        };
+
+        regions
+            .0
+            .iter()
+            .filter(|(_, region)| region.world == world)
+            .filter_map(
+                |(name, region)| match (region.contains(from), region.contains(to)) {
+                    (false, true) => Some((RegionEnter.name(), name.clone())),
+                    (true, false) => Some((RegionLeave.name(), name.clone())),
+                    _ => None,
+                },
+            )
+            .collect()
+    };
+
+    // Leave callbacks run before enter callbacks, each in region name order.
+    transitions
+        .sort_by_key(|(event_type, region)| (*event_type == RegionEnter.name(), region.clone()));
+
+    let mut cancelled = false;
+    for (event_type, region) in transitions {
+        let event_data: EventPayload = Arc::new(RegionData {
+            player_name: player_name.to_string(),
+            player_uuid: player_uuid.to_string(),
+            region,
+            world: world.to_string(),
+            x: to.0,
+            y: to.1,
+            z: to.2,
+        });
+
+        let outcome = trigger_event(lua, event_type, &ListenerPriority::ALL, &event_data).await;
+        cancelled |= outcome.cancelled;
+    }
+
+    Ok(cancelled)
+}
+
+/// Fired from movement events when a player moves into a region.
+pub struct RegionEnter;
+
+#[async_trait]
+impl LuaEvent for RegionEnter {
+    fn name(&self) -> &'static str {
+        "region_enter"
+    }
+
+    async fn register(&self, _context: &Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<RegionData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
+
+/// Fired from movement events when a player moves out of a region.
+pub struct RegionLeave;
+
+#[async_trait]
+impl LuaEvent for RegionLeave {
+    fn name(&self) -> &'static str {
+        "region_leave"
+    }
+
+    async fn register(&self, _context: &Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<RegionData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
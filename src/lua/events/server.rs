@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use mlua::{IntoLua, Lua, Table, Value};
+use pumpkin::plugin::Context;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::SERVER;
+use crate::lua;
+use crate::lua::events::{
+    EventOutcome, EventPayload, LuaEvent, call_listeners, dispatch_event_within, fire_event,
+    object::{EventFields, trigger_typed_event},
+    priority::ListenerPriority,
+};
+use crate::lua::worker::LuaCommand;
+
+/// The interval between two `tick` events, matching the server's 20 ticks per second.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the measured ticks per second are updated.
+const TPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// How long the server waits for the `server_stopping` listeners.
+const STOPPING_TIMEOUT: Duration = Duration::from_secs(10);
+
+static TICK_TASK: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+#[derive(Clone)]
+struct TickData {
+    tick: u64,
+    tps: f64,
+}
+
+impl EventFields for TickData {
+    fn get(&self, lua: &Lua, key: &str) -> mlua::Result<Value> {
+        match key {
+            "tick" => self.tick.into_lua(lua),
+            "tps" => self.tps.into_lua(lua),
+            _ => Ok(Value::Nil),
+        }
+    }
+}
+
+/// Fires a `tick` event every [`TICK_INTERVAL`] once the server is available,
+/// measuring how many ticks actually ran per second.
+///
+/// Pumpkin does not expose its tick loop to plugins, so this is PLua's own
+/// timer running at the server's nominal rate. The measured rate drops when
+/// the runtime PLua shares with the server is overloaded, but not when only
+/// the server's tick loop lags.
+async fn run_tick_loop() {
+    while SERVER.get().is_none() {
+        tokio::time::sleep(TICK_INTERVAL).await;
+    }
+
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut tick = 0;
+    let mut tps = 1.0 / TICK_INTERVAL.as_secs_f64();
+    let mut window_start = Instant::now();
+    let mut window_ticks = 0;
+
+    loop {
+        interval.tick().await;
+
+        tick += 1;
+        window_ticks += 1;
+
+        let elapsed = window_start.elapsed();
+        if elapsed >= TPS_WINDOW {
+            tps = f64::from(window_ticks) / elapsed.as_secs_f64();
+            window_start = Instant::now();
+            window_ticks = 0;
+        }
+
+        fire_event(&Tick, &ListenerPriority::ALL, TickData { tick, tps });
+    }
+}
+
+/// Runs the `server_started` listeners once Pumpkin has loaded the plugins.
+///
+/// The event goes through the worker's management channel, so it is handled
+/// after the worker has enabled its plugins and after the direct plugins
+/// Pumpkin enabled before this call.
+pub fn started() {
+    let command = LuaCommand::TriggerEvent {
+        event_type: ServerStarted.name().to_string(),
+        tiers: &ListenerPriority::ALL,
+        event_data: Arc::new(()),
+        response: None,
+    };
+
+    if let Err(e) = lua::manager().and_then(|manager| manager.send_command(command)) {
+        log::error!("Failed to send server_started event to Lua: {}", e);
+    }
+}
+
+/// Stops the tick loop and runs the `server_stopping` listeners, waiting for
+/// them so plugins can flush their state before the server shuts down.
+pub async fn shutdown() {
+    if let Some(task) = TICK_TASK.lock().unwrap().take() {
+        task.abort();
+    }
+
+    dispatch_event_within(
+        &ServerStopping,
+        &ListenerPriority::ALL,
+        (),
+        STOPPING_TIMEOUT,
+    )
+    .await;
+}
+
+async fn trigger_lifecycle_event(
+    lua: &Lua,
+    event_type: &str,
+    listeners: &Table,
+    tiers: &[ListenerPriority],
+) -> mlua::Result<EventOutcome> {
+    let event_table = lua.create_table()?;
+    call_listeners(lua, event_type, listeners, &event_table, tiers).await?;
+
+    Ok(EventOutcome::default())
+}
+
+/// Fired once the server has loaded and enabled the Lua plugins.
+pub struct ServerStarted;
+
+#[async_trait]
+impl LuaEvent for ServerStarted {
+    fn name(&self) -> &'static str {
+        "server_started"
+    }
+
+    async fn register(&self, _context: &Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        _event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_lifecycle_event(lua, self.name(), listeners, tiers).await
+    }
+}
+
+/// Fired when the server unloads PLua during shutdown.
+pub struct ServerStopping;
+
+#[async_trait]
+impl LuaEvent for ServerStopping {
+    fn name(&self) -> &'static str {
+        "server_stopping"
+    }
+
+    async fn register(&self, _context: &Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        _event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_lifecycle_event(lua, self.name(), listeners, tiers).await
+    }
+}
+
+/// Fired 20 times per second with the tick number and the measured ticks per second.
+pub struct Tick;
+
+#[async_trait]
+impl LuaEvent for Tick {
+    fn name(&self) -> &'static str {
+        "tick"
+    }
+
+    async fn register(&self, _context: &Context) -> Result<(), String> {
+        let mut task = TICK_TASK.lock().unwrap();
+        if task.is_none() {
+            *task = Some(tokio::spawn(run_tick_loop()));
+        }
+
+        Ok(())
+    }
+
+    async fn trigger_event(
+        &self,
+        lua: &Lua,
+        listeners: &Table,
+        event_data: &EventPayload,
+        tiers: &[ListenerPriority],
+    ) -> mlua::Result<EventOutcome> {
+        trigger_typed_event::<TickData>(lua, self.name(), listeners, event_data, tiers).await
+    }
+}
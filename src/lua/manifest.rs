@@ -1,5 +1,7 @@
 use mlua::{FromLua, prelude::*};
 
+use crate::lua::permissions::LuaPermission;
+
 pub struct LuaPluginManifest {
     pub name: String,
     pub description: String,
@@ -7,11 +9,21 @@ pub struct LuaPluginManifest {
     pub author: String,
     pub on_enable: Option<LuaFunction>,
     pub on_disable: Option<LuaFunction>,
+    pub permissions: Vec<LuaPermission>,
 }
 
 impl FromLua for LuaPluginManifest {
     fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
         let table = LuaTable::from_lua(value, lua)?;
+
+        let mut permissions = Vec::new();
+        if let Some(declared) = table.get::<Option<LuaTable>>("permissions")? {
+            for pair in declared.pairs::<String, Option<LuaTable>>() {
+                let (node, options) = pair?;
+                permissions.push(LuaPermission::from_table(node, options)?);
+            }
+        }
+
         Ok(LuaPluginManifest {
             name: table.get("name")?,
             description: table.get("description").unwrap_or_else(|_| String::new()),
@@ -21,6 +33,7 @@ impl FromLua for LuaPluginManifest {
                 .unwrap_or_else(|_| "Unknown".to_string()),
             on_enable: table.get("on_enable")?,
             on_disable: table.get("on_disable")?,
+            permissions,
         })
     }
 }
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use mlua::Lua;
+
+use crate::config::MemoryLimitConfig;
+
+fn mebibytes(mb: u64) -> usize {
+    usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+}
+
+/// Memory limits and the memory attributed to each plugin, stored as app data.
+///
+/// Luau cannot tell which plugin owns an allocation, so the change in memory
+/// used between two interrupts is attributed to the plugin running at the
+/// time. Memory freed by the garbage collector while another plugin runs
+/// would leave the accounting too high, so it is scaled down whenever it
+/// exceeds the memory actually in use.
+struct Memory {
+    default: Option<usize>,
+    plugins: HashMap<String, usize>,
+    used: HashMap<String, usize>,
+    last_total: usize,
+}
+
+impl Memory {
+    fn limit(&self, plugin: &str) -> Option<usize> {
+        self.plugins.get(plugin).copied().or(self.default)
+    }
+
+    fn record(&mut self, plugin: &str, total: usize) {
+        let delta = total as i128 - self.last_total as i128;
+        self.last_total = total;
+
+        let used = self.used.entry(plugin.to_string()).or_default();
+        *used = (*used as i128 + delta).max(0) as usize;
+
+        let attributed: usize = self.used.values().sum();
+        if attributed > total {
+            for used in self.used.values_mut() {
+                *used = (*used as u128 * total as u128 / attributed as u128) as usize;
+            }
+        }
+    }
+}
+
+/// Limits the memory of the Lua state and sets up per-plugin accounting.
+pub fn install(lua: &Lua, config: &MemoryLimitConfig) {
+    let total = config.total_mb.map(mebibytes);
+    if let Some(Err(e)) = total.map(|limit| lua.set_memory_limit(limit)) {
+        log::warn!("Failed to limit the memory of Lua plugins: {}", e);
+    }
+
+    lua.set_app_data(Memory {
+        default: config.default_mb.map(mebibytes),
+        plugins: config
+            .plugins
+            .iter()
+            .map(|(name, mb)| (name.clone(), mebibytes(*mb)))
+            .collect(),
+        used: HashMap::new(),
+        last_total: lua.used_memory(),
+    });
+}
+
+/// Attributes the memory allocated since the last call to the running plugin,
+/// failing with a memory error if the plugin is over its limit.
+pub(crate) fn account(lua: &Lua, plugin: Option<&str>) -> mlua::Result<()> {
+    let total = lua.used_memory();
+    let Some(mut memory) = lua.app_data_mut::<Memory>() else {
+        return Ok(());
+    };
+
+    let Some(plugin) = plugin else {
+        memory.last_total = total;
+        return Ok(());
+    };
+
+    memory.record(plugin, total);
+
+    let used = memory.used.get(plugin).copied().unwrap_or(0);
+    match memory.limit(plugin) {
+        Some(limit) if used > limit => Err(mlua::Error::MemoryError(format!(
+            "not enough memory: plugin {} uses {} of its {} limit",
+            plugin,
+            format_bytes(used),
+            format_bytes(limit)
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Stops accounting memory to a plugin that was disabled.
+pub fn forget_plugin(lua: &Lua, plugin_name: &str) {
+    if let Some(mut memory) = lua.app_data_mut::<Memory>() {
+        memory.used.remove(plugin_name);
+    }
+}
+
+/// The memory attributed to a plugin, in bytes.
+pub fn usage(lua: &Lua, plugin_name: &str) -> usize {
+    lua.app_data_ref::<Memory>()
+        .and_then(|memory| memory.used.get(plugin_name).copied())
+        .unwrap_or(0)
+}
+
+/// The memory limit of a plugin, in bytes.
+pub fn limit(lua: &Lua, plugin_name: &str) -> Option<usize> {
+    lua.app_data_ref::<Memory>()
+        .and_then(|memory| memory.limit(plugin_name))
+}
+
+/// Formats a number of bytes in KiB or MiB.
+pub fn format_bytes(bytes: usize) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    if bytes as f64 >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
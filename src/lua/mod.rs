@@ -1,214 +1,203 @@
+pub mod budget;
+pub mod commands;
+pub mod eval;
 pub mod events;
 pub mod manifest;
+pub mod memory;
+pub mod permissions;
 pub mod runtime;
+pub mod scheduler;
+pub mod supervisor;
 pub mod worker;
 
-use std::path::PathBuf;
-use std::sync::Once;
-use std::sync::mpsc;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use std::time::Duration;
-use tokio::sync::broadcast::{self, Sender};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
 
 use anyhow::{Result, anyhow};
 
-use self::worker::{LuaCommand, run_lua_worker};
+use self::supervisor::LuaManagerHandle;
+use self::worker::{EVAL_TIMEOUT, LuaCommand, PluginInfo};
 
-static INIT: Once = Once::new();
-static mut COMMAND_SENDER: Option<Sender<LuaCommand>> = None;
-static mut WORKER_HANDLE: Option<tokio::task::JoinHandle<()>> = None;
-static mut IS_INITIALIZED: bool = false;
+/// How many events can wait for the Lua worker. When it falls further behind,
+/// it misses the oldest ones, which are counted as dropped.
+pub const COMMAND_CHANNEL_CAPACITY: usize = 1024;
 
-pub fn init_lua_manager(data_dir: String) -> Result<()> {
-    INIT.call_once(|| {
-        let (tx, rx) = broadcast::channel::<LuaCommand>(64);
+/// How long requests to the Lua worker wait for its response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
-        unsafe {
-            COMMAND_SENDER = Some(tx.clone());
-        }
+/// How long a reload of all plugins may take.
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(10);
 
-        let data_path = data_dir.clone();
-        let tx = tx.clone();
-        let handle = tokio::spawn(async move {
-            run_lua_worker(rx, tx, data_path).await;
-        });
+static MANAGER: RwLock<Option<LuaManagerHandle>> = RwLock::new(None);
 
-        unsafe {
-            WORKER_HANDLE = Some(handle);
-            IS_INITIALIZED = true;
-        }
-    });
+/// Starts the Lua worker, unless it is already running.
+pub fn init_lua_manager(data_dir: String) -> Result<()> {
+    let mut manager = MANAGER
+        .write()
+        .map_err(|_| anyhow!("Lua manager lock poisoned"))?;
 
-    unsafe {
-        if !IS_INITIALIZED {
-            return Err(anyhow!(
-                "Lua manager initialization failed - no valid Lua state"
-            ));
-        }
+    if manager.is_none() {
+        *manager = Some(LuaManagerHandle::start(PathBuf::from(data_dir)));
     }
 
     Ok(())
 }
 
-fn get_sender() -> Result<Sender<LuaCommand>> {
-    unsafe {
-        if !IS_INITIALIZED {
-            return Err(anyhow!(
-                "LuaManager not initialized or initialization failed"
-            ));
-        }
-
-        #[allow(static_mut_refs)]
-        if let Some(sender) = COMMAND_SENDER.as_ref() {
-            Ok(sender.clone())
-        } else {
-            Err(anyhow!("LuaManager not initialized"))
-        }
+/// Disables every plugin and stops the Lua worker. It can be started again
+/// with `init_lua_manager`.
+pub async fn shutdown_lua_manager() {
+    let manager = MANAGER.write().ok().and_then(|mut manager| manager.take());
+    if let Some(manager) = manager {
+        manager.shutdown().await;
     }
 }
 
-pub fn reload() -> Result<()> {
-    let sender = get_sender()?;
-
-    if unsafe { !IS_INITIALIZED } {
-        return Err(anyhow!(
-            "Lua runtime not properly initialized - no valid Lua state"
-        ));
-    }
-
-    let (tx, rx) = mpsc::channel();
-    sender
-        .send(LuaCommand::Reload { response: tx })
-        .map_err(|_| anyhow!("Failed to send command to Lua worker"))?;
-
-    rx.recv_timeout(Duration::from_secs(10))
-        .map_err(|_| anyhow!("Lua worker disconnected or reload timed out"))?
+/// Returns a handle to the running Lua worker.
+pub fn manager() -> Result<LuaManagerHandle> {
+    MANAGER
+        .read()
+        .ok()
+        .and_then(|manager| manager.clone())
+        .ok_or_else(|| anyhow!("LuaManager not initialized"))
 }
 
-pub fn get_plugin_list() -> Vec<(String, bool)> {
-    let sender = match get_sender() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting sender: {}", e);
-            return Vec::new();
-        }
-    };
-
-    let (tx, rx) = mpsc::channel();
-    if let Err(e) = sender.send(LuaCommand::GetPluginList { response: tx }) {
-        eprintln!("Failed to send command to Lua worker: {}", e);
-        return Vec::new();
-    }
+pub async fn reload() -> Result<()> {
+    request(RELOAD_TIMEOUT, |response| LuaCommand::Reload { response }).await?
+}
 
-    match rx.recv() {
+pub async fn get_plugin_list() -> Vec<(String, bool)> {
+    match request(REQUEST_TIMEOUT, |response| LuaCommand::GetPluginList {
+        response,
+    })
+    .await
+    {
         Ok(list) => list,
         Err(e) => {
-            eprintln!("Lua worker disconnected: {}", e);
+            log::warn!("Failed to list Lua plugins: {}", e);
             Vec::new()
         }
     }
 }
 
-pub fn enable_plugin(name: &str) -> Result<bool> {
-    let sender = get_sender()?;
-
-    if unsafe { !IS_INITIALIZED } {
-        return Err(anyhow!(
-            "Lua runtime not properly initialized - no valid Lua state"
-        ));
-    }
-
-    let (tx, rx) = mpsc::channel();
-    sender
-        .send(LuaCommand::EnablePlugin {
-            name: name.to_string(),
-            response: tx,
-        })
-        .map_err(|_| anyhow!("Failed to send command to Lua worker"))?;
+pub async fn enable_plugin(name: &str) -> Result<bool> {
+    request(REQUEST_TIMEOUT, |response| LuaCommand::EnablePlugin {
+        name: name.to_string(),
+        response,
+    })
+    .await?
+}
 
-    let result = rx.recv_timeout(std::time::Duration::from_secs(5));
-    match result {
-        Ok(plugin_result) => plugin_result,
-        Err(_) => Err(anyhow!(
-            "Lua worker disconnected, timed out, or failed to process request"
-        )),
-    }
+pub async fn disable_plugin(name: &str) -> Result<bool> {
+    request(REQUEST_TIMEOUT, |response| LuaCommand::DisablePlugin {
+        name: name.to_string(),
+        response,
+    })
+    .await?
 }
 
-pub fn disable_plugin(name: &str) -> Result<bool> {
-    let sender = get_sender()?;
+pub async fn reload_plugin(name: &str) -> Result<bool> {
+    request(REQUEST_TIMEOUT, |response| LuaCommand::ReloadPlugin {
+        name: name.to_string(),
+        response,
+    })
+    .await?
+}
 
-    if unsafe { !IS_INITIALIZED } {
-        return Err(anyhow!(
-            "Lua runtime not properly initialized - no valid Lua state"
-        ));
+pub async fn get_plugin_info(name: &str) -> Option<PluginInfo> {
+    match request(REQUEST_TIMEOUT, |response| LuaCommand::GetPluginInfo {
+        name: name.to_string(),
+        response,
+    })
+    .await
+    {
+        Ok(info) => info,
+        Err(e) => {
+            log::warn!("Failed to get info of Lua plugin {}: {}", name, e);
+            None
+        }
     }
+}
 
-    let (tx, rx) = mpsc::channel();
-    sender
-        .send(LuaCommand::DisablePlugin {
-            name: name.to_string(),
-            response: tx,
-        })
-        .map_err(|_| anyhow!("Failed to send command to Lua worker"))?;
+/// Evaluates code as a plugin. The worker gives up on code that waits for
+/// longer than `EVAL_TIMEOUT`, while code that never pauses is aborted by the
+/// plugin's execution budget, so the worker stays responsive either way.
+pub async fn eval(plugin: &str, code: &str) -> Result<String> {
+    request(EVAL_TIMEOUT + REQUEST_TIMEOUT, |response| {
+        LuaCommand::Eval {
+            plugin: plugin.to_string(),
+            code: code.to_string(),
+            response,
+        }
+    })
+    .await?
+}
 
-    let result = rx.recv_timeout(std::time::Duration::from_secs(5));
-    match result {
-        Ok(plugin_result) => plugin_result,
+/// Sends a command to the Lua worker and waits for its response without
+/// blocking the async runtime. A command that times out stays queued and is
+/// still handled once the worker gets to it.
+async fn request<T>(
+    timeout: Duration,
+    command: impl FnOnce(UnboundedSender<T>) -> LuaCommand,
+) -> Result<T> {
+    let sender = manager()?;
+
+    let (tx, mut rx) = unbounded_channel();
+    sender.send_command(command(tx))?;
+
+    match tokio::time::timeout(timeout, rx.recv()).await {
+        Ok(Some(response)) => Ok(response),
+        Ok(None) => Err(anyhow!("Lua worker disconnected")),
         Err(_) => Err(anyhow!(
-            "Lua worker disconnected, timed out, or failed to process request"
+            "Lua worker did not respond within {} seconds",
+            timeout.as_secs()
         )),
     }
 }
 
-pub fn reload_plugin(name: &str) -> Result<bool> {
-    let sender = get_sender()?;
-
-    if unsafe { !IS_INITIALIZED } {
-        return Err(anyhow!(
-            "Lua runtime not properly initialized - no valid Lua state"
-        ));
-    }
+/// Adds a plugin that Pumpkin loaded from the server's plugins directory. It
+/// is remembered so the worker gets it back after a restart.
+pub async fn add_direct_plugin(path: &Path) -> Result<PluginInfo> {
+    let info = request(REQUEST_TIMEOUT, |response| LuaCommand::AddDirectPlugin {
+        path: path.to_path_buf(),
+        response,
+    })
+    .await??;
+
+    manager()?.track_direct_plugin(&info.name, path.to_path_buf());
+    Ok(info)
+}
 
-    let (tx, rx) = mpsc::channel();
-    sender
-        .send(LuaCommand::ReloadPlugin {
-            name: name.to_string(),
-            response: tx,
-        })
-        .map_err(|_| anyhow!("Failed to send command to Lua worker"))?;
+pub async fn enable_direct_plugin(name: &str) -> Result<bool> {
+    let enabled = request(REQUEST_TIMEOUT, |response| LuaCommand::EnableDirectPlugin {
+        name: name.to_string(),
+        response,
+    })
+    .await??;
 
-    let result = rx.recv_timeout(std::time::Duration::from_secs(5));
-    match result {
-        Ok(plugin_result) => plugin_result,
-        Err(_) => Err(anyhow!(
-            "Lua worker disconnected, timed out, or failed to process request"
-        )),
-    }
+    manager()?.set_direct_plugin_enabled(name, true);
+    Ok(enabled)
 }
 
-pub fn get_plugin_info(name: &str) -> Option<(String, String, String, String, bool, PathBuf)> {
-    let sender = match get_sender() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting sender: {}", e);
-            return None;
+pub async fn disable_direct_plugin(name: &str) -> Result<bool> {
+    manager()?.set_direct_plugin_enabled(name, false);
+
+    request(REQUEST_TIMEOUT, |response| {
+        LuaCommand::DisableDirectPlugin {
+            name: name.to_string(),
+            response,
         }
-    };
+    })
+    .await?
+}
 
-    let (tx, rx) = mpsc::channel();
-    if let Err(e) = sender.send(LuaCommand::GetPluginInfo {
-        name: name.to_string(),
-        response: tx,
-    }) {
-        eprintln!("Failed to send command to Lua worker: {}", e);
-        return None;
-    }
+pub async fn remove_direct_plugin(name: &str) -> Result<()> {
+    manager()?.forget_direct_plugin(name);
 
-    match rx.recv() {
-        Ok(info) => info,
-        Err(e) => {
-            eprintln!("Lua worker disconnected: {}", e);
-            None
-        }
-    }
+    request(REQUEST_TIMEOUT, |response| LuaCommand::RemoveDirectPlugin {
+        name: name.to_string(),
+        response,
+    })
+    .await?
 }
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use mlua::{Lua, Table, Value};
+use pumpkin::{PERMISSION_REGISTRY, entity::player::Player};
+use pumpkin_util::{
+    PermissionLvl,
+    permission::{Permission, PermissionDefault},
+};
+
+use crate::SERVER;
+use crate::lua::runtime::active_plugin;
+
+/// The nodes registered by Lua plugins, with the plugin that registered each.
+/// They are removed from Pumpkin when their plugin is disabled.
+static REGISTERED: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Default::default);
+
+/// A permission node declared by a Lua plugin.
+#[derive(Clone)]
+pub struct LuaPermission {
+    pub node: String,
+    pub description: String,
+    pub default: PermissionDefault,
+}
+
+impl LuaPermission {
+    /// Reads a permission from its node and a table with the optional
+    /// `description` and `default` fields.
+    pub fn from_table(node: String, options: Option<Table>) -> mlua::Result<Self> {
+        let (description, default) = match options {
+            Some(options) => (
+                options.get::<Option<String>>("description")?,
+                options.get::<Value>("default")?,
+            ),
+            None => (None, Value::Nil),
+        };
+
+        Ok(Self {
+            node,
+            description: description.unwrap_or_default(),
+            default: permission_default(default)?,
+        })
+    }
+}
+
+pub fn level_number(level: PermissionLvl) -> u8 {
+    level as u8
+}
+
+fn level_from_number(level: i64) -> Option<PermissionLvl> {
+    Some(match level {
+        0 => PermissionLvl::Zero,
+        1 => PermissionLvl::One,
+        2 => PermissionLvl::Two,
+        3 => PermissionLvl::Three,
+        4 => PermissionLvl::Four,
+        _ => return None,
+    })
+}
+
+/// Reads who has a permission by default: `"allow"`, `"deny"`, `"op"` for
+/// operators of level 4 or an operator level from 0 to 4. Defaults to `"op"`.
+fn permission_default(value: Value) -> mlua::Result<PermissionDefault> {
+    let level = match value {
+        Value::Nil => return Ok(PermissionDefault::Op(PermissionLvl::Four)),
+        Value::String(name) => match name.to_str()?.as_ref() {
+            "allow" => return Ok(PermissionDefault::Allow),
+            "deny" => return Ok(PermissionDefault::Deny),
+            "op" => return Ok(PermissionDefault::Op(PermissionLvl::Four)),
+            _ => None,
+        },
+        Value::Integer(level) => level_from_number(level.into()),
+        Value::Number(level) if level.fract() == 0.0 => level_from_number(level as i64),
+        _ => None,
+    };
+
+    level.map(PermissionDefault::Op).ok_or_else(|| {
+        mlua::Error::RuntimeError(
+            "Permission defaults are \"allow\", \"deny\", \"op\" or a level from 0 to 4".into(),
+        )
+    })
+}
+
+/// Registers a permission of a plugin with Pumpkin, returning false if the
+/// plugin already registered the node. The node's namespace must be the
+/// plugin's name.
+pub async fn register(plugin_name: &str, permission: &LuaPermission) -> mlua::Result<bool> {
+    let owned = permission
+        .node
+        .split_once(':')
+        .is_some_and(|(namespace, _)| namespace.eq_ignore_ascii_case(plugin_name));
+    if !owned {
+        return Err(mlua::Error::RuntimeError(format!(
+            "Permission node {} must be in the namespace of its plugin, like {}:node",
+            permission.node,
+            plugin_name.to_ascii_lowercase()
+        )));
+    }
+
+    {
+        let mut registered = REGISTERED.lock().unwrap();
+        if registered.contains_key(&permission.node) {
+            return Ok(false);
+        }
+        registered.insert(permission.node.clone(), plugin_name.to_string());
+    }
+
+    let result = PERMISSION_REGISTRY
+        .write()
+        .await
+        .register_permission(Permission::new(
+            &permission.node,
+            &permission.description,
+            permission.default.clone(),
+        ));
+
+    if let Err(e) = result {
+        REGISTERED.lock().unwrap().remove(&permission.node);
+        return Err(mlua::Error::RuntimeError(format!(
+            "Failed to register permission {}: {}",
+            permission.node, e
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Registers the permissions declared in a plugin manifest, logging failures.
+pub async fn register_all(plugin_name: &str, permissions: &[LuaPermission]) {
+    for permission in permissions {
+        if let Err(e) = register(plugin_name, permission).await {
+            log::error!("Plugin {}: {}", plugin_name, e);
+        }
+    }
+}
+
+/// Removes the given nodes from Pumpkin's permission registry.
+async fn unregister(nodes: Vec<String>) -> usize {
+    let mut registry = PERMISSION_REGISTRY.write().await;
+    for node in &nodes {
+        registry.unregister_permission(node);
+    }
+
+    nodes.len()
+}
+
+/// Removes every permission registered by a plugin, returning how many were
+/// removed.
+pub async fn remove_plugin_permissions(plugin_name: &str) -> usize {
+    let nodes: Vec<String> = {
+        let mut registered = REGISTERED.lock().unwrap();
+        let nodes = registered
+            .iter()
+            .filter(|(_, owner)| *owner == plugin_name)
+            .map(|(node, _)| node.clone())
+            .collect::<Vec<_>>();
+        for node in &nodes {
+            registered.remove(node);
+        }
+        nodes
+    };
+
+    unregister(nodes).await
+}
+
+/// Removes every permission registered by Lua plugins, returning how many
+/// were removed.
+pub async fn remove_all_permissions() -> usize {
+    let nodes = REGISTERED
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(node, _)| node)
+        .collect();
+
+    unregister(nodes).await
+}
+
+/// Finds an online player by name or UUID.
+async fn find_player(player: &str) -> Option<Arc<Player>> {
+    let server = SERVER.get()?;
+    server.get_all_players().await.into_iter().find(|online| {
+        online.gameprofile.name == player || online.gameprofile.id.to_string() == player
+    })
+}
+
+/// Creates the `pumpkin.permissions` table.
+pub fn setup_lua_api(lua: &Lua, pumpkin_table: &Table) -> mlua::Result<()> {
+    let permissions_table = lua.create_table()?;
+
+    permissions_table.set(
+        "register",
+        lua.create_async_function(
+            |lua_ctx, (node, options): (String, Option<Table>)| async move {
+                let Some(plugin_name) = active_plugin(&lua_ctx) else {
+                    return Err(mlua::Error::RuntimeError(
+                        "Permissions can only be registered by a plugin".into(),
+                    ));
+                };
+
+                register(&plugin_name, &LuaPermission::from_table(node, options)?).await
+            },
+        )?,
+    )?;
+
+    permissions_table.set(
+        "has",
+        lua.create_async_function(|_, (player, node): (String, String)| async move {
+            Ok(match find_player(&player).await {
+                Some(player) => player.has_permission(&node).await,
+                None => false,
+            })
+        })?,
+    )?;
+
+    permissions_table.set(
+        "level",
+        lua.create_async_function(|_, player: String| async move {
+            Ok(find_player(&player)
+                .await
+                .map(|player| level_number(player.permission_lvl.load())))
+        })?,
+    )?;
+
+    pumpkin_table.set("permissions", permissions_table)?;
+
+    Ok(())
+}
@@ -1,21 +1,363 @@
-use anyhow::{Context as AnyhowContext, Result};
-use mlua::{Function, Lua, Table};
+use anyhow::{Context as AnyhowContext, Result, anyhow};
+use mlua::{FromLuaMulti, Function, IntoLuaMulti, Lua, Table, Thread};
 use pumpkin_util::text::TextComponent;
 use rand::{Rng, rng};
 use std::collections::HashMap;
 use std::fs;
+use std::future::poll_fn;
 use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::SERVER;
 use crate::config::ConfigManager;
+use crate::lua::budget;
+use crate::lua::commands;
 use crate::lua::events;
+use crate::lua::events::priority::ListenerPriority;
 use crate::lua::manifest::LuaPluginManifest;
+use crate::lua::memory;
+use crate::lua::permissions;
+use crate::lua::scheduler;
+
+/// Registration counter used to keep listeners of the same priority in order.
+static LISTENER_ORDER: AtomicU64 = AtomicU64::new(0);
+
+/// The plugins that coroutines run code for, stored as app data and keyed by
+/// coroutine. API functions use it to attribute the resources they create,
+/// such as event listeners. Coroutines of different plugins interleave
+/// whenever one of them awaits, so it is tracked per coroutine rather than as
+/// a single active plugin.
+#[derive(Default)]
+struct Owners(HashMap<usize, Vec<String>>);
+
+fn thread_key(thread: &Thread) -> usize {
+    thread.to_pointer() as usize
+}
+
+/// Identifies the running coroutine.
+pub(crate) fn current_thread_key(lua: &Lua) -> usize {
+    thread_key(&lua.current_thread())
+}
+
+/// Forgets the owner and the budget of a coroutine once its call ends, even if
+/// the code that ran for a plugin was cancelled halfway.
+struct CallGuard<'a> {
+    lua: &'a Lua,
+    thread: usize,
+}
+
+impl Drop for CallGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(mut owners) = self.lua.app_data_mut::<Owners>() {
+            owners.0.remove(&self.thread);
+        }
+        budget::end_call(self.lua, self.thread);
+    }
+}
+
+/// Makes `plugin_name` the owner of the running coroutine until `leave_plugin`
+/// is called.
+fn enter_plugin(lua: &Lua, plugin_name: String) {
+    let thread = current_thread_key(lua);
+    if let Some(mut owners) = lua.app_data_mut::<Owners>() {
+        owners.0.entry(thread).or_default().push(plugin_name);
+    }
+}
+
+/// Restores the owner the running coroutine had before `enter_plugin`.
+fn leave_plugin(lua: &Lua) {
+    let thread = current_thread_key(lua);
+    if let Some(mut owners) = lua.app_data_mut::<Owners>() {
+        let emptied = owners.0.get_mut(&thread).is_some_and(|stack| {
+            stack.pop();
+            stack.is_empty()
+        });
+
+        if emptied {
+            owners.0.remove(&thread);
+        }
+    }
+}
+
+/// Calls `function` in a coroutine of its own that runs for `plugin_name`, so
+/// everything it creates is attributed to that plugin. The call gets a fresh
+/// execution budget every time the coroutine is resumed, when it starts and
+/// after every wait.
+pub async fn call_as_plugin<R: FromLuaMulti>(
+    lua: &Lua,
+    plugin_name: Option<&str>,
+    function: &Function,
+    args: impl IntoLuaMulti,
+) -> mlua::Result<R> {
+    let thread = lua.create_thread(function.clone())?;
+    let key = thread_key(&thread);
+
+    if let (Some(name), Some(mut owners)) = (plugin_name, lua.app_data_mut::<Owners>()) {
+        owners.0.insert(key, vec![name.to_string()]);
+    }
+    let _guard = CallGuard { lua, thread: key };
+
+    // Every poll resumes the coroutine, and it is only polled again after it
+    // waited on something
+    let mut call = pin!(thread.into_async::<R>(args)?);
+    poll_fn(|cx| {
+        let _resumed = budget::resume_call(lua, key);
+        call.as_mut().poll(cx)
+    })
+    .await
+}
+
+/// The plugin the running coroutine executes code for.
+pub fn active_plugin(lua: &Lua) -> Option<String> {
+    let thread = current_thread_key(lua);
+    lua.app_data_ref::<Owners>().and_then(|owners| {
+        owners
+            .0
+            .get(&thread)
+            .and_then(|stack| stack.last().cloned())
+    })
+}
+
+/// The registry key of the shared `pumpkin` API table. Plugins only see it
+/// through the read-only proxy in their environment.
+const API_KEY: &str = "plua.api";
+
+/// The environments plugins run in, stored as app data and keyed by plugin name.
+#[derive(Default)]
+struct Environments(HashMap<String, Table>);
+
+/// Wraps the `pumpkin` API in a read-only proxy whose functions run as the
+/// given plugin, so everything they create is attributed to it even when
+/// several plugins' coroutines are interleaved. The previous owner of the
+/// coroutine is restored once a function returns or fails.
+const BIND_API: &str = r#"
+local api, owner, enter, leave = ...
+
+local function finish(ok, ...)
+    leave()
+    if not ok then
+        error((...), 0)
+    end
+    return ...
+end
+
+local function bind(source)
+    local cache = {}
+    return setmetatable({}, {
+        __index = function(_, key)
+            local cached = cache[key]
+            if cached ~= nil then
+                return cached
+            end
+
+            local value = source[key]
+            if type(value) == "function" then
+                cached = function(...)
+                    enter(owner)
+                    return finish(pcall(value, ...))
+                end
+            elseif type(value) == "table" then
+                cached = bind(value)
+            else
+                return value
+            end
+
+            cache[key] = cached
+            return cached
+        end,
+        __newindex = function()
+            error("The pumpkin API is read-only", 2)
+        end,
+        __metatable = false,
+    })
+end
+
+return bind(api)
+"#;
+
+/// Stands in for the `pumpkin` API while a plugin's manifest is read. Every
+/// field is the stub again and calling it does nothing, so top-level code can
+/// use the API without registering listeners, tasks or commands.
+const STUB_API: &str = r#"
+local stub = {}
+return setmetatable(stub, {
+    __index = function()
+        return stub
+    end,
+    __call = function() end,
+    __newindex = function() end,
+})
+"#;
+
+/// Creates an environment table that reads missing globals from the shared
+/// ones. Its metatable is locked so code cannot reach the shared globals
+/// through it, and `_G` is the environment itself.
+fn environment(lua: &Lua) -> mlua::Result<Table> {
+    let env = lua.create_table()?;
+    let metatable = lua.create_table()?;
+    metatable.set("__index", lua.globals())?;
+    metatable.set("__metatable", false)?;
+    env.set_metatable(Some(metatable));
+    env.set("_G", env.clone())?;
+    Ok(env)
+}
+
+/// Returns the environment a plugin's code runs in, creating it on first use.
+/// Globals a plugin defines stay in its environment, and its `pumpkin` table is
+/// bound to the plugin.
+pub fn plugin_environment(lua: &Lua, plugin_name: &str) -> mlua::Result<Table> {
+    if let Some(env) = lua
+        .app_data_ref::<Environments>()
+        .and_then(|environments| environments.0.get(plugin_name).cloned())
+    {
+        return Ok(env);
+    }
+
+    let enter = lua.create_function(|lua_ctx, owner: String| {
+        enter_plugin(lua_ctx, owner);
+        Ok(())
+    })?;
+    let leave = lua.create_function(|lua_ctx, ()| {
+        leave_plugin(lua_ctx);
+        Ok(())
+    })?;
+
+    let api: Table = lua.load(BIND_API).set_name("=bind_api").call((
+        lua.named_registry_value::<Table>(API_KEY)?,
+        plugin_name,
+        enter,
+        leave,
+    ))?;
+
+    let env = environment(lua)?;
+    env.set("pumpkin", api)?;
+
+    if let Some(mut environments) = lua.app_data_mut::<Environments>() {
+        environments.0.insert(plugin_name.to_string(), env.clone());
+    }
+
+    Ok(env)
+}
+
+/// Drops everything a plugin registered through the API so nothing it set up
+/// outlives it being disabled, reloaded or unloaded.
+pub async fn release_plugin_resources(lua: &Lua, plugin_name: &str) {
+    if let Some(mut environments) = lua.app_data_mut::<Environments>() {
+        environments.0.remove(plugin_name);
+    }
+    memory::forget_plugin(lua, plugin_name);
+
+    match events::remove_plugin_listeners(lua, plugin_name) {
+        Ok(0) => {}
+        Ok(removed) => log::debug!(
+            "Removed {} event listeners owned by plugin {}",
+            removed,
+            plugin_name
+        ),
+        Err(e) => log::error!(
+            "Failed to remove event listeners of plugin {}: {}",
+            plugin_name,
+            e
+        ),
+    }
+
+    let regions = events::region::remove_plugin_regions(lua, plugin_name);
+    if regions > 0 {
+        log::debug!(
+            "Removed {} regions owned by plugin {}",
+            regions,
+            plugin_name
+        );
+    }
+
+    let tasks = scheduler::cancel_plugin_tasks(lua, plugin_name);
+    if tasks > 0 {
+        log::debug!(
+            "Cancelled {} scheduled tasks owned by plugin {}",
+            tasks,
+            plugin_name
+        );
+    }
+
+    let commands = commands::remove_plugin_commands(lua, plugin_name);
+    if commands > 0 {
+        log::debug!(
+            "Unregistered {} commands owned by plugin {}",
+            commands,
+            plugin_name
+        );
+    }
+
+    let permissions = permissions::remove_plugin_permissions(plugin_name).await;
+    if permissions > 0 {
+        log::debug!(
+            "Unregistered {} permissions owned by plugin {}",
+            permissions,
+            plugin_name
+        );
+    }
+}
+
+/// Releases what the plugins of an abandoned Lua state registered outside of
+/// it, so scheduled tasks and commands stop running its code, and the
+/// restarted plugins can register their permissions and listeners again.
+pub async fn release_all_resources(lua: &Lua) {
+    let tasks = scheduler::cancel_all_tasks(lua);
+    let commands = commands::remove_all_commands(lua);
+    let permissions = permissions::remove_all_permissions().await;
+    events::counts::clear();
+
+    log::debug!(
+        "Cancelled {} scheduled tasks and unregistered {} commands and {} permissions of an abandoned Lua state",
+        tasks,
+        commands,
+        permissions
+    );
+}
+
+/// Reads the `priority` field of the options passed to `register_listener`.
+fn listener_priority(options: Option<Table>) -> mlua::Result<ListenerPriority> {
+    let Some(name) = options
+        .map(|options| options.get::<Option<String>>("priority"))
+        .transpose()?
+        .flatten()
+    else {
+        return Ok(ListenerPriority::Normal);
+    };
+
+    ListenerPriority::from_name(&name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("Unknown listener priority: {}", name)))
+}
+
+/// Returns the listener table of a built-in or plugin-defined event.
+fn listener_table(lua: &Lua, event_type: &str) -> mlua::Result<Table> {
+    if !events::is_known(lua, event_type) {
+        return Err(mlua::Error::RuntimeError(format!(
+            "Unknown event type: {}",
+            event_type
+        )));
+    }
+
+    events::listener_table(lua, event_type)
+}
+
+/// Where a plugin was found, which decides what enables it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PluginSource {
+    /// Found in PLua's plugins directory and enabled through `config.json`.
+    Managed,
+    /// Loaded by Pumpkin from the server's plugins directory, and enabled and
+    /// disabled along with the server.
+    Direct,
+}
 
 pub struct LuaPlugin {
     pub manifest: LuaPluginManifest,
     pub file_path: PathBuf,
     pub enabled: bool,
+    pub source: PluginSource,
 }
 
 pub struct LuaRuntime {
@@ -39,9 +381,24 @@ impl LuaRuntime {
         })
     }
 
+    /// Reads the manifests of the plugins in PLua's plugins directory, and
+    /// reads those of direct plugins again.
     pub fn discover_plugins(&mut self) -> Result<()> {
+        let direct: Vec<PathBuf> = self
+            .plugins
+            .values()
+            .filter(|plugin| plugin.source == PluginSource::Direct)
+            .map(|plugin| plugin.file_path.clone())
+            .collect();
+
         self.plugins.clear();
 
+        for path in direct {
+            if let Err(e) = self.load_plugin_metadata(&path, PluginSource::Direct) {
+                log::error!("Failed to load plugin {:?}: {}", path, e);
+            }
+        }
+
         let entries =
             fs::read_dir(&self.plugins_dir).context("Failed to read plugins directory")?;
 
@@ -49,41 +406,102 @@ impl LuaRuntime {
             let entry = entry.context("Failed to read directory entry")?;
             let path = entry.path();
 
-            if path.is_file() && path.extension().is_some_and(|ext| ext == "lua") {
-                self.load_plugin_metadata(&path)?;
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "lua") {
+                continue;
+            }
+
+            if let Err(e) = self.load_plugin_metadata(&path, PluginSource::Managed) {
+                log::error!("Failed to load plugin {:?}: {}", path, e);
             }
         }
 
         Ok(())
     }
 
-    fn load_plugin_metadata(&mut self, path: &Path) -> Result<()> {
+    /// Adds a plugin that Pumpkin loaded from the server's plugins directory,
+    /// returning its name. It stays disabled until Pumpkin enables it.
+    pub fn add_direct_plugin(&mut self, path: &Path) -> Result<String> {
+        self.load_plugin_metadata(path, PluginSource::Direct)
+    }
+
+    /// Disables and forgets a plugin that Pumpkin unloaded.
+    pub async fn remove_direct_plugin(&mut self, name: &str) -> Result<()> {
+        let result = self.disable_plugin(name).await;
+        self.plugins.remove(name);
+        result.map(|_| ())
+    }
+
+    /// The names of the enabled direct plugins, which a reload enables again.
+    pub fn enabled_direct_plugins(&self) -> Vec<String> {
+        self.plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.enabled && plugin.source == PluginSource::Direct)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn load_plugin_metadata(&mut self, path: &Path, source: PluginSource) -> Result<String> {
         let script = fs::read_to_string(path)
             .with_context(|| format!("Failed to read plugin file: {:?}", path))?;
 
-        // Only used for metadata extraction
-        let manifest = self
-            .lua
-            .load(&script)
-            .set_name(path.file_name().unwrap().to_string_lossy().as_ref())
-            .eval::<LuaPluginManifest>()?;
+        // Only used for metadata extraction, in a throwaway environment so the
+        // script cannot change the shared globals, and with a stub API so its
+        // top-level code registers nothing
+        let env = environment(&self.lua)?;
+        env.set(
+            "pumpkin",
+            self.lua
+                .load(STUB_API)
+                .set_name("=stub_api")
+                .eval::<Table>()?,
+        )?;
+
+        // The top-level code runs against the default execution budget
+        let thread = current_thread_key(&self.lua);
+        let manifest = {
+            let _resumed = budget::resume_call(&self.lua, thread);
+            self.lua
+                .load(&script)
+                .set_name(path.file_name().unwrap().to_string_lossy().as_ref())
+                .set_environment(env)
+                .eval::<LuaPluginManifest>()
+        };
+        budget::end_call(&self.lua, thread);
+        let manifest = manifest?;
+
+        let name = manifest.name.clone();
+        let other = self
+            .plugins
+            .get(&name)
+            .filter(|existing| existing.file_path != path);
+        if let Some(existing) = other {
+            return Err(anyhow!(
+                "Plugin {} is already loaded from {:?}",
+                name,
+                existing.file_path
+            ));
+        }
 
         let plugin = LuaPlugin {
             manifest,
             file_path: path.to_path_buf(),
             enabled: false,
+            source,
         };
 
-        self.plugins.insert(plugin.manifest.name.clone(), plugin);
+        self.plugins.insert(name.clone(), plugin);
 
-        Ok(())
+        Ok(name)
     }
 
     pub fn init_api(&self) -> Result<()> {
         let lua = &self.lua;
 
+        lua.set_app_data(Owners::default());
+        lua.set_app_data(Environments::default());
+
         let pumpkin_table = lua.create_table()?;
-        lua.globals().set("pumpkin", pumpkin_table.clone())?;
+        lua.set_named_registry_value(API_KEY, pumpkin_table.clone())?;
 
         {
             let log_table = lua.create_table()?;
@@ -147,123 +565,79 @@ impl LuaRuntime {
 
             events_table.set(
                 "register_listener",
-                lua.create_function(|lua_ctx, (event_type, callback): (String, Function)| {
-                    let globals = lua_ctx.globals();
-                    let pumpkin: Table = globals.get("pumpkin")?;
-                    let events: Table = pumpkin.get("events")?;
-
-                    let timestamp = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis();
-
-                    let random = rng().random::<u32>();
-
-                    let plugin_name = lua_ctx
-                        .globals()
-                        .get::<Table>("PLUGIN_INFO")
-                        .and_then(|t| t.get::<String>("name"))
-                        .unwrap_or_else(|_| "unknown".to_string());
-
-                    let callback_name = callback
-                        .info()
-                        .name
-                        .unwrap_or_else(|| event_type.clone())
-                        .replace(|c: char| !c.is_alphanumeric(), "");
-
-                    let listener_id = format!(
-                        "listener_{}_{}_{}_{}",
-                        plugin_name, callback_name, timestamp, random
-                    );
-
-                    match event_type.as_str() {
-                        "player_join" => {
-                            let listeners: Table = events.get("player_join")?;
-                            listeners.set(listener_id.clone(), callback)?;
-                            Ok(listener_id)
-                        }
-                        "player_leave" => {
-                            let listeners: Table = events.get("player_leave")?;
-                            listeners.set(listener_id.clone(), callback)?;
-                            Ok(listener_id)
-                        }
-                        "player_chat" => {
-                            let listeners: Table = events.get("player_chat")?;
-                            listeners.set(listener_id.clone(), callback)?;
-                            Ok(listener_id)
-                        }
-                        "block_place" => {
-                            let listeners: Table = events.get("block_place")?;
-                            listeners.set(listener_id.clone(), callback)?;
-                            Ok(listener_id)
-                        }
-                        "block_break" => {
-                            let listeners: Table = events.get("block_break")?;
-                            listeners.set(listener_id.clone(), callback)?;
-                            Ok(listener_id)
-                        }
-                        _ => Err(mlua::Error::RuntimeError(format!(
-                            "Unknown event type: {}",
-                            event_type
-                        ))),
-                    }
-                })?,
+                lua.create_function(
+                    |lua_ctx, (event_type, callback, opts): (String, Function, Option<Table>)| {
+                        let priority = listener_priority(opts)?;
+
+                        let timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_millis();
+
+                        let random = rng().random::<u32>();
+
+                        let owner = active_plugin(lua_ctx);
+                        let plugin_name = owner.as_deref().unwrap_or("unknown");
+
+                        let callback_name = callback
+                            .info()
+                            .name
+                            .unwrap_or_else(|| event_type.clone())
+                            .replace(|c: char| !c.is_alphanumeric(), "");
+
+                        let listener_id = format!(
+                            "listener_{}_{}_{}_{}",
+                            plugin_name, callback_name, timestamp, random
+                        );
+
+                        let entry = lua_ctx.create_table()?;
+                        entry.set("callback", callback)?;
+                        entry.set("priority", priority.name())?;
+                        entry.set("order", LISTENER_ORDER.fetch_add(1, Ordering::Relaxed))?;
+                        entry.set("owner", owner)?;
+
+                        let listeners = listener_table(lua_ctx, &event_type)?;
+                        events::count_listener(&event_type, &entry);
+                        listeners.set(listener_id.clone(), entry)?;
+                        Ok(listener_id)
+                    },
+                )?,
             )?;
 
             events_table.set(
                 "unregister_listener",
                 lua.create_function(|lua_ctx, (event_type, listener_id): (String, String)| {
-                    let globals = lua_ctx.globals();
-                    let pumpkin: Table = globals.get("pumpkin")?;
-                    let events: Table = pumpkin.get("events")?;
-
-                    match event_type.as_str() {
-                        "player_join" => {
-                            let listeners: Table = events.get("player_join")?;
-                            listeners.set(listener_id, mlua::Value::Nil)?;
-                            Ok(true)
-                        }
-                        "player_leave" => {
-                            let listeners: Table = events.get("player_leave")?;
-                            listeners.set(listener_id, mlua::Value::Nil)?;
-                            Ok(true)
-                        }
-                        "player_chat" => {
-                            let listeners: Table = events.get("player_chat")?;
-                            listeners.set(listener_id, mlua::Value::Nil)?;
-                            Ok(true)
-                        }
-                        "block_place" => {
-                            let listeners: Table = events.get("block_place")?;
-                            listeners.set(listener_id, mlua::Value::Nil)?;
-                            Ok(true)
-                        }
-                        "block_break" => {
-                            let listeners: Table = events.get("block_break")?;
-                            listeners.set(listener_id, mlua::Value::Nil)?;
-                            Ok(true)
-                        }
-                        _ => Err(mlua::Error::RuntimeError(format!(
-                            "Unknown event type: {}",
-                            event_type
-                        ))),
-                    }
+                    let listeners = listener_table(lua_ctx, &event_type)?;
+                    let Some(entry) = listeners.get::<Option<Table>>(listener_id.as_str())? else {
+                        return Ok(false);
+                    };
+
+                    events::forget_listener(&event_type, &entry);
+                    listeners.set(listener_id, mlua::Value::Nil)?;
+                    Ok(true)
                 })?,
             )?;
 
-            events::player_join::setup_lua_event(lua, &events_table)?;
-            events::player_leave::setup_lua_event(lua, &events_table)?;
-            events::player_chat::setup_lua_event(lua, &events_table)?;
-            events::block_place::setup_lua_event(lua, &events_table)?;
-            events::block_break::setup_lua_event(lua, &events_table)?;
+            events_table.set(
+                "list",
+                lua.create_function(|lua_ctx, ()| Ok(events::event_names(lua_ctx)))?,
+            )?;
+
+            events::setup_lua_events(lua, &events_table)?;
 
             pumpkin_table.set("events", events_table)?;
         }
 
+        events::region::setup_lua_api(lua, &pumpkin_table)?;
+        scheduler::setup_lua_api(lua, &pumpkin_table)?;
+        commands::setup_lua_api(lua, &pumpkin_table)?;
+        permissions::setup_lua_api(lua, &pumpkin_table)?;
+
         Ok(())
     }
 
-    pub fn enable_plugin(&mut self, name: &str) -> Result<bool> {
+    pub async fn enable_plugin(&mut self, name: &str) -> Result<bool> {
+        let lua = &self.lua;
         if let Some(plugin) = self.plugins.get_mut(name) {
             if plugin.enabled {
                 return Ok(false);
@@ -272,25 +646,50 @@ impl LuaRuntime {
             let script = fs::read_to_string(&plugin.file_path)
                 .with_context(|| format!("Failed to read plugin file: {:?}", plugin.file_path))?;
 
-            self.lua
-                .load(&script)
-                .set_name(
-                    plugin
-                        .file_path
-                        .file_name()
-                        .unwrap()
-                        .to_string_lossy()
-                        .as_ref(),
-                )
-                .exec()
-                .with_context(|| {
-                    format!("Failed to execute plugin script: {:?}", plugin.file_path)
-                })?;
-
-            if let Some(on_enable) = &plugin.manifest.on_enable {
-                on_enable
-                    .call::<()>(())
-                    .with_context(|| format!("Failed to call on_enable for plugin {}", name))?;
+            permissions::register_all(name, &plugin.manifest.permissions).await;
+
+            let env = plugin_environment(lua, name)?;
+
+            let result = async {
+                // The manifest returned here holds the lifecycle functions
+                // defined in the plugin's environment
+                let chunk = lua
+                    .load(&script)
+                    .set_name(
+                        plugin
+                            .file_path
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .as_ref(),
+                    )
+                    .set_environment(env)
+                    .into_function()
+                    .with_context(|| {
+                        format!("Failed to load plugin script: {:?}", plugin.file_path)
+                    })?;
+
+                plugin.manifest = call_as_plugin(lua, Some(name), &chunk, ())
+                    .await
+                    .with_context(|| {
+                        format!("Failed to execute plugin script: {:?}", plugin.file_path)
+                    })?;
+
+                if let Some(on_enable) = &plugin.manifest.on_enable {
+                    call_as_plugin::<()>(lua, Some(name), on_enable, ())
+                        .await
+                        .with_context(|| format!("Failed to call on_enable for plugin {}", name))?;
+                }
+
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+
+            // A plugin that failed to enable is never disabled, so whatever it
+            // registered before failing has to be released here
+            if let Err(e) = result {
+                release_plugin_resources(lua, name).await;
+                return Err(e);
             }
 
             plugin.enabled = true;
@@ -301,19 +700,25 @@ impl LuaRuntime {
         }
     }
 
-    pub fn disable_plugin(&mut self, name: &str) -> Result<bool> {
+    pub async fn disable_plugin(&mut self, name: &str) -> Result<bool> {
         if let Some(plugin) = self.plugins.get_mut(name) {
             if !plugin.enabled {
                 return Ok(false);
             }
 
-            if let Some(on_disable) = &plugin.manifest.on_disable {
-                on_disable
-                    .call::<()>(())
-                    .with_context(|| format!("Failed to call on_disable for plugin {}", name))?;
-            }
+            let on_disable_result = match &plugin.manifest.on_disable {
+                Some(on_disable) => {
+                    call_as_plugin::<()>(&self.lua, Some(name), on_disable, ()).await
+                }
+                None => Ok(()),
+            };
 
+            release_plugin_resources(&self.lua, name).await;
             plugin.enabled = false;
+
+            on_disable_result
+                .with_context(|| format!("Failed to call on_disable for plugin {}", name))?;
+
             Ok(true)
         } else {
             log::warn!("Attempted to disable unknown plugin: {}", name);
@@ -321,11 +726,11 @@ impl LuaRuntime {
         }
     }
 
-    pub fn load_enabled_plugins(&mut self, config_manager: &ConfigManager) -> Result<()> {
+    pub async fn load_enabled_plugins(&mut self, config_manager: &ConfigManager) -> Result<()> {
         for plugin_name in &config_manager.config.enabled_plugins {
             if let Some(plugin) = self.plugins.get(plugin_name) {
                 if !plugin.enabled {
-                    if let Err(e) = self.enable_plugin(plugin_name) {
+                    if let Err(e) = self.enable_plugin(plugin_name).await {
                         log::error!("Failed to enable plugin {}: {}", plugin_name, e);
                     }
                 }
@@ -337,7 +742,7 @@ impl LuaRuntime {
         Ok(())
     }
 
-    pub fn disable_all_plugins(&mut self) -> Result<()> {
+    pub async fn disable_all_plugins(&mut self) -> Result<()> {
         let mut to_disable = vec![];
 
         for (name, plugin) in &mut self.plugins {
@@ -347,7 +752,7 @@ impl LuaRuntime {
         }
 
         for name in to_disable {
-            if let Err(e) = self.disable_plugin(name.as_str()) {
+            if let Err(e) = self.disable_plugin(name.as_str()).await {
                 log::error!("Failed to disable plugin {}: {}", name, e);
             }
         }
@@ -355,7 +760,7 @@ impl LuaRuntime {
         Ok(())
     }
 
-    pub fn reload_plugin(&mut self, name: &str) -> Result<bool> {
+    pub async fn reload_plugin(&mut self, name: &str) -> Result<bool> {
         let was_enabled = if let Some(plugin) = self.plugins.get(name) {
             plugin.enabled
         } else {
@@ -363,21 +768,22 @@ impl LuaRuntime {
         };
 
         if was_enabled {
-            self.disable_plugin(name)?;
+            self.disable_plugin(name).await?;
         }
 
-        let fp = {
+        let (fp, source) = {
             let plugin = self.plugins.get(name);
             if plugin.is_none() {
                 return Ok(false);
             }
-            plugin.unwrap().file_path.clone()
+            let plugin = plugin.unwrap();
+            (plugin.file_path.clone(), plugin.source)
         };
 
-        self.load_plugin_metadata(&fp)?;
+        self.load_plugin_metadata(&fp, source)?;
 
         if was_enabled {
-            self.enable_plugin(name)?;
+            self.enable_plugin(name).await?;
         }
 
         Ok(true)
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use mlua::{Function, Lua, MultiValue, Table, UserData, UserDataFields, UserDataMethods, Value};
+use tokio::task::AbortHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::lua::events::server::TICK_INTERVAL;
+use crate::lua::runtime::{active_plugin, call_as_plugin};
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+tokio::task_local! {
+    /// The id of the scheduled task being run, used to only allow waiting in tasks.
+    static CURRENT_TASK: u64;
+}
+
+struct ScheduledTask {
+    owner: Option<String>,
+    handle: AbortHandle,
+}
+
+/// The tasks scheduled in a Lua state, stored as app data. Finished tasks are
+/// pruned whenever a new one is scheduled.
+#[derive(Default)]
+struct Tasks(HashMap<u64, ScheduledTask>);
+
+/// The handle returned to Lua for a scheduled task.
+struct TaskHandle(u64);
+
+impl UserData for TaskHandle {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("id", |_, this| Ok(this.0));
+        fields.add_field_method_get("active", |lua, this| {
+            Ok(lua.app_data_ref::<Tasks>().is_some_and(|tasks| {
+                tasks
+                    .0
+                    .get(&this.0)
+                    .is_some_and(|task| !task.handle.is_finished())
+            }))
+        });
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("cancel", |lua, this, ()| Ok(cancel_task(lua, this.0)));
+    }
+}
+
+fn ticks(ticks: u32) -> Duration {
+    TICK_INTERVAL * ticks
+}
+
+/// Cancels a task, returning whether it was still scheduled.
+fn cancel_task(lua: &Lua, id: u64) -> bool {
+    let Some(task) = lua
+        .app_data_mut::<Tasks>()
+        .and_then(|mut tasks| tasks.0.remove(&id))
+    else {
+        return false;
+    };
+
+    let running = !task.handle.is_finished();
+    task.handle.abort();
+    running
+}
+
+/// Reads the argument of `pumpkin.task.wait`: a number of ticks or a table
+/// with a `seconds` field.
+fn wait_duration(value: Value) -> mlua::Result<Duration> {
+    match value {
+        Value::Integer(count) if count >= 0 => Ok(ticks(count.try_into().unwrap_or(u32::MAX))),
+        Value::Number(count) if count >= 0.0 => Ok(TICK_INTERVAL.mul_f64(count)),
+        Value::Table(duration) => {
+            let seconds: f64 = duration.get("seconds")?;
+            Duration::try_from_secs_f64(seconds)
+                .map_err(|e| mlua::Error::RuntimeError(format!("Invalid wait duration: {}", e)))
+        }
+        _ => Err(mlua::Error::RuntimeError(
+            "Expected a number of ticks or { seconds = ... }".into(),
+        )),
+    }
+}
+
+/// Runs a task callback as the plugin that scheduled it.
+async fn run_callback(lua: &Lua, owner: Option<&str>, callback: &Function) {
+    run_callback_with(lua, owner, callback, MultiValue::new()).await;
+}
+
+async fn run_callback_with(lua: &Lua, owner: Option<&str>, callback: &Function, args: MultiValue) {
+    if let Err(e) = call_as_plugin::<()>(lua, owner, callback, args).await {
+        log::error!(
+            "Error in scheduled task of plugin {}: {}",
+            owner.unwrap_or("unknown"),
+            e
+        );
+    }
+}
+
+/// Spawns a task owned by the active plugin and returns its handle.
+fn schedule<F>(lua: &Lua, task: impl FnOnce(Lua, Option<String>) -> F) -> mlua::Result<TaskHandle>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    if lua.app_data_ref::<Tasks>().is_none() {
+        return Err(mlua::Error::RuntimeError(
+            "Scheduler not initialized".into(),
+        ));
+    }
+
+    let owner = active_plugin(lua);
+    let id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+    let handle =
+        tokio::spawn(CURRENT_TASK.scope(id, task(lua.clone(), owner.clone()))).abort_handle();
+
+    if let Some(mut tasks) = lua.app_data_mut::<Tasks>() {
+        tasks.0.retain(|_, task| !task.handle.is_finished());
+        tasks.0.insert(id, ScheduledTask { owner, handle });
+    }
+
+    Ok(TaskHandle(id))
+}
+
+/// Creates the `pumpkin.scheduler` table.
+pub fn setup_lua_api(lua: &Lua, pumpkin_table: &Table) -> mlua::Result<()> {
+    lua.set_app_data(Tasks::default());
+
+    let scheduler_table = lua.create_table()?;
+
+    scheduler_table.set(
+        "run_later",
+        lua.create_function(|lua_ctx, (delay, callback): (u32, Function)| {
+            schedule(lua_ctx, move |lua, owner| async move {
+                tokio::time::sleep(ticks(delay)).await;
+                run_callback(&lua, owner.as_deref(), &callback).await;
+            })
+        })?,
+    )?;
+
+    scheduler_table.set(
+        "run_repeating",
+        lua.create_function(|lua_ctx, (delay, period, callback): (u32, u32, Function)| {
+            if period == 0 {
+                return Err(mlua::Error::RuntimeError(
+                    "The period of a repeating task must be at least one tick".into(),
+                ));
+            }
+
+            schedule(lua_ctx, move |lua, owner| async move {
+                tokio::time::sleep(ticks(delay)).await;
+
+                let mut interval = tokio::time::interval(ticks(period));
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                loop {
+                    interval.tick().await;
+                    run_callback(&lua, owner.as_deref(), &callback).await;
+                }
+            })
+        })?,
+    )?;
+
+    scheduler_table.set(
+        "run_async",
+        lua.create_function(|lua_ctx, callback: Function| {
+            schedule(lua_ctx, move |lua, owner| async move {
+                run_callback(&lua, owner.as_deref(), &callback).await;
+            })
+        })?,
+    )?;
+
+    pumpkin_table.set("scheduler", scheduler_table)?;
+
+    let task_table = lua.create_table()?;
+
+    task_table.set(
+        "spawn",
+        lua.create_function(|lua_ctx, (callback, args): (Function, MultiValue)| {
+            schedule(lua_ctx, move |lua, owner| async move {
+                run_callback_with(&lua, owner.as_deref(), &callback, args).await;
+            })
+        })?,
+    )?;
+
+    task_table.set(
+        "wait",
+        lua.create_async_function(|_, duration: Value| async move {
+            if CURRENT_TASK.try_with(|_| ()).is_err() {
+                return Err(mlua::Error::RuntimeError(
+                    "pumpkin.task.wait can only be used inside a task".into(),
+                ));
+            }
+
+            tokio::time::sleep(wait_duration(duration)?).await;
+            Ok(())
+        })?,
+    )?;
+
+    pumpkin_table.set("task", task_table)?;
+
+    Ok(())
+}
+
+/// Cancels every task scheduled by a plugin, returning how many were running.
+pub fn cancel_plugin_tasks(lua: &Lua, plugin_name: &str) -> usize {
+    let Some(mut tasks) = lua.app_data_mut::<Tasks>() else {
+        return 0;
+    };
+
+    let mut cancelled = 0;
+    tasks.0.retain(|_, task| {
+        if task.owner.as_deref() != Some(plugin_name) {
+            return true;
+        }
+
+        if !task.handle.is_finished() {
+            task.handle.abort();
+            cancelled += 1;
+        }
+        false
+    });
+    cancelled
+}
+
+/// Cancels every scheduled task, returning how many were running.
+pub fn cancel_all_tasks(lua: &Lua) -> usize {
+    let Some(mut tasks) = lua.app_data_mut::<Tasks>() else {
+        return 0;
+    };
+
+    tasks
+        .0
+        .drain()
+        .filter(|(_, task)| {
+            let running = !task.handle.is_finished();
+            task.handle.abort();
+            running
+        })
+        .count()
+}
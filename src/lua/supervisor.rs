@@ -0,0 +1,223 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+
+use super::COMMAND_CHANNEL_CAPACITY;
+use super::runtime::release_all_resources;
+use super::worker::{LuaCommand, LuaManager, run_lua_worker};
+
+/// How long the supervisor waits before restarting a crashed worker. The
+/// delay doubles with every crash in a row.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest delay between restarts. A worker that ran longer than this
+/// before crashing is restarted after `RESTART_DELAY` again.
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// How long the plugins get to run `on_disable` when the worker shuts down
+/// before it is aborted.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A plugin that Pumpkin loaded from the server's plugins directory. These
+/// are remembered outside of the worker so a restarted worker gets them back.
+#[derive(Clone)]
+pub struct DirectPlugin {
+    pub path: PathBuf,
+    pub enabled: bool,
+}
+
+/// A handle to the Lua worker. Clones share the same worker, which is run by
+/// a supervisor that restarts it when it crashes, so handles stay valid
+/// across restarts.
+///
+/// Events go through a bounded broadcast channel that drops the oldest ones
+/// when the worker falls behind. Management commands such as enabling a
+/// plugin go through their own unbounded channel, so none of them is lost.
+#[derive(Clone)]
+pub struct LuaManagerHandle {
+    events: Sender<LuaCommand>,
+    control: UnboundedSender<LuaCommand>,
+    shutdown: Arc<Notify>,
+    direct_plugins: Arc<Mutex<HashMap<String, DirectPlugin>>>,
+}
+
+impl LuaManagerHandle {
+    /// Starts the supervisor and its worker for the given data directory.
+    pub fn start(data_dir: PathBuf) -> Self {
+        let (events, events_rx) = broadcast::channel(COMMAND_CHANNEL_CAPACITY);
+        let (control, control_rx) = unbounded_channel();
+
+        let handle = Self {
+            events,
+            control,
+            shutdown: Arc::new(Notify::new()),
+            direct_plugins: Arc::default(),
+        };
+
+        tokio::spawn(supervise(
+            handle.clone(),
+            events_rx,
+            Arc::new(AsyncMutex::new(control_rx)),
+            data_dir,
+        ));
+
+        handle
+    }
+
+    /// Sends an event to the worker, returning how many receivers got it.
+    pub fn send_event(&self, command: LuaCommand) -> Result<usize> {
+        self.events
+            .send(command)
+            .map_err(|_| anyhow!("Failed to send event to Lua worker"))
+    }
+
+    /// Sends a management command to the worker. It is queued until the
+    /// worker handles it, also while the worker restarts.
+    pub fn send_command(&self, command: LuaCommand) -> Result<()> {
+        self.control
+            .send(command)
+            .map_err(|_| anyhow!("Failed to send command to Lua worker"))
+    }
+
+    /// How many events are queued for the worker.
+    pub fn backlog(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Disables every plugin, then stops the worker and its supervisor. A
+    /// worker that does not finish within `SHUTDOWN_TIMEOUT` is aborted.
+    pub async fn shutdown(&self) {
+        let (response, mut done) = unbounded_channel();
+        if self.send_command(LuaCommand::Shutdown { response }).is_ok()
+            && tokio::time::timeout(SHUTDOWN_TIMEOUT, done.recv())
+                .await
+                .is_err()
+        {
+            log::warn!(
+                "Lua plugins did not shut down within {} seconds",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+        }
+
+        self.shutdown.notify_one();
+    }
+
+    pub fn track_direct_plugin(&self, name: &str, path: PathBuf) {
+        self.direct_plugins.lock().unwrap().insert(
+            name.to_string(),
+            DirectPlugin {
+                path,
+                enabled: false,
+            },
+        );
+    }
+
+    pub fn set_direct_plugin_enabled(&self, name: &str, enabled: bool) {
+        if let Some(plugin) = self.direct_plugins.lock().unwrap().get_mut(name) {
+            plugin.enabled = enabled;
+        }
+    }
+
+    pub fn forget_direct_plugin(&self, name: &str) {
+        self.direct_plugins.lock().unwrap().remove(name);
+    }
+
+    fn direct_plugins(&self) -> Vec<DirectPlugin> {
+        self.direct_plugins
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".into()
+    }
+}
+
+/// Runs the worker, restarting it whenever it panics or fails, and retrying
+/// when it cannot be created. A restarted worker discovers the plugins again
+/// and enables those listed in `config.json`, along with the direct plugins
+/// Pumpkin had enabled.
+async fn supervise(
+    handle: LuaManagerHandle,
+    mut rx: Receiver<LuaCommand>,
+    control: Arc<AsyncMutex<UnboundedReceiver<LuaCommand>>>,
+    data_dir: PathBuf,
+) {
+    let mut delay = RESTART_DELAY;
+
+    loop {
+        let started = Instant::now();
+
+        let failure = match LuaManager::new(&data_dir) {
+            Ok(manager) => {
+                let lua = manager.runtime.lua.clone();
+                let mut worker = tokio::spawn(run_lua_worker(
+                    rx,
+                    control.clone(),
+                    manager,
+                    handle.direct_plugins(),
+                ));
+
+                let result = tokio::select! {
+                    result = &mut worker => result,
+                    _ = handle.shutdown.notified() => {
+                        worker.abort();
+                        release_all_resources(&lua).await;
+                        return;
+                    }
+                };
+
+                let failure = match result {
+                    Ok(Ok(())) => {
+                        log::info!("Lua worker stopped");
+                        return;
+                    }
+                    Ok(Err(e)) => format!("Lua worker failed: {}", e),
+                    Err(e) if e.is_panic() => {
+                        format!("Lua worker crashed: {}", panic_message(e.into_panic()))
+                    }
+                    Err(_) => return,
+                };
+
+                // Scheduled tasks and commands keep the stopped worker's Lua
+                // state alive and would keep running its plugins
+                release_all_resources(&lua).await;
+                failure
+            }
+            Err(e) => format!("Failed to initialize LuaManager: {}", e),
+        };
+
+        // Events sent from now on wait for the restarted worker, while
+        // management commands stay queued in the control channel
+        rx = handle.events.subscribe();
+
+        if started.elapsed() > MAX_RESTART_DELAY {
+            delay = RESTART_DELAY;
+        }
+
+        log::error!("{}. Restarting in {} seconds", failure, delay.as_secs());
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = handle.shutdown.notified() => return,
+        }
+
+        delay = (delay * 2).min(MAX_RESTART_DELAY);
+    }
+}
@@ -1,16 +1,39 @@
 use std::cell::RefCell;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use std::sync::{Mutex, Once};
-use tokio::sync::broadcast::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
 use anyhow::{Result, anyhow};
 
-use super::events;
-use super::runtime::LuaRuntime;
+use super::budget;
+use super::commands::{self, CommandArg, LuaCommandSender};
+use super::eval;
+use super::events::{self, EventOutcome, EventPayload, delivery, priority::ListenerPriority};
+use super::memory;
+use super::runtime::{LuaPlugin, LuaRuntime, PluginSource};
+use super::supervisor::DirectPlugin;
 use crate::config::ConfigManager;
 
-pub type PluginInfo = (String, String, String, String, bool, PathBuf);
+/// What `/plua info` shows about a plugin.
+pub struct PluginInfo {
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub author: String,
+    pub enabled: bool,
+    pub file_path: PathBuf,
+    pub budget_overruns: u32,
+    pub memory_used: usize,
+    pub memory_limit: Option<usize>,
+}
+
+/// How long an evaluated snippet may wait before it is abandoned. A timeout
+/// cannot interrupt Lua code that runs without pausing, so busy loops are
+/// stopped by the plugin's execution budget instead.
+pub const EVAL_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct LuaManager {
     pub runtime: LuaRuntime,
@@ -53,57 +76,100 @@ impl LuaManager {
 #[derive(Clone)]
 pub enum LuaCommand {
     Reload {
-        response: mpsc::Sender<Result<()>>,
+        response: UnboundedSender<Result<()>>,
     },
     GetPluginList {
-        response: mpsc::Sender<Vec<(String, bool)>>,
+        response: UnboundedSender<Vec<(String, bool)>>,
     },
     EnablePlugin {
         name: String,
-        response: mpsc::Sender<Result<bool>>,
+        response: UnboundedSender<Result<bool>>,
     },
     DisablePlugin {
         name: String,
-        response: mpsc::Sender<Result<bool>>,
+        response: UnboundedSender<Result<bool>>,
     },
     ReloadPlugin {
         name: String,
-        response: mpsc::Sender<Result<bool>>,
+        response: UnboundedSender<Result<bool>>,
     },
     GetPluginInfo {
         name: String,
-        response: mpsc::Sender<Option<PluginInfo>>,
+        response: UnboundedSender<Option<PluginInfo>>,
+    },
+    Eval {
+        plugin: String,
+        code: String,
+        response: UnboundedSender<Result<String>>,
+    },
+    /// Sent by the loader when Pumpkin loads a plugin from the server's
+    /// plugins directory.
+    AddDirectPlugin {
+        path: PathBuf,
+        response: UnboundedSender<Result<PluginInfo>>,
+    },
+    EnableDirectPlugin {
+        name: String,
+        response: UnboundedSender<Result<bool>>,
+    },
+    DisableDirectPlugin {
+        name: String,
+        response: UnboundedSender<Result<bool>>,
+    },
+    RemoveDirectPlugin {
+        name: String,
+        response: UnboundedSender<Result<()>>,
+    },
+    /// Sent when a command registered by a plugin is run.
+    RunCommand {
+        owner: Option<String>,
+        callback: u64,
+        sender: LuaCommandSender,
+        arguments: Vec<(String, CommandArg)>,
+        response: UnboundedSender<mlua::Result<()>>,
     },
+    /// Sent while a player types an argument of a plugin's command.
+    SuggestCommand {
+        owner: Option<String>,
+        callback: u64,
+        sender: LuaCommandSender,
+        partial: String,
+        response: UnboundedSender<mlua::Result<Vec<String>>>,
+    },
+    /// Disables every plugin and stops the worker once they are disabled.
+    Shutdown { response: UnboundedSender<()> },
+    /// Sent when a plugin exceeded its execution budget too often. The plugin
+    /// is disabled until it is enabled again, but stays enabled in
+    /// `config.json`.
+    SuspendPlugin { name: String },
     TriggerEvent {
         event_type: String,
-        event_data: String,
+        tiers: &'static [ListenerPriority],
+        event_data: EventPayload,
+        response: Option<UnboundedSender<EventOutcome>>,
     },
 }
 
+/// Runs the Lua worker until it is shut down. Returns an error when the Lua
+/// runtime cannot be set up, so the supervisor can try again.
 pub async fn run_lua_worker(
     mut rx: Receiver<LuaCommand>,
-    tx: Sender<LuaCommand>,
-    data_dir: String,
-) {
-    let data_path = PathBuf::from(data_dir);
-
-    init_event_sender(tx);
-
-    let manager = match LuaManager::new(&data_path) {
-        Ok(m) => Mutex::new(m),
-        Err(e) => {
-            eprintln!("Failed to initialize LuaManager: {}", e);
-            return;
-        }
-    };
+    control: Arc<Mutex<UnboundedReceiver<LuaCommand>>>,
+    manager: LuaManager,
+    direct_plugins: Vec<DirectPlugin>,
+) -> Result<()> {
+    let manager = Mutex::new(manager);
 
     {
-        let mut lock = manager.lock().unwrap();
+        let mut lock = manager.lock().await;
 
-        if let Err(e) = lock.runtime.init_api() {
-            eprintln!("Failed to initialize Lua API: {}", e);
-            return;
-        }
+        lock.runtime
+            .init_api()
+            .map_err(|e| anyhow!("Failed to initialize Lua API: {}", e))?;
+
+        let config = &lock.config_manager.config;
+        memory::install(&lock.runtime.lua, &config.memory_limit);
+        budget::install(&lock.runtime.lua, &config.execution_budget);
 
         lock.initialized = true;
 
@@ -113,56 +179,157 @@ pub async fn run_lua_worker(
     }
 
     let config_manager_clone = {
-        let lock = manager.lock().unwrap();
+        let lock = manager.lock().await;
         lock.config_manager.clone()
     };
 
     {
-        let mut lock = manager.lock().unwrap();
-        if let Err(e) = lock.runtime.load_enabled_plugins(&config_manager_clone) {
+        let mut lock = manager.lock().await;
+        if let Err(e) = lock
+            .runtime
+            .load_enabled_plugins(&config_manager_clone)
+            .await
+        {
             eprintln!("Failed to load enabled plugins at startup: {}", e);
         }
+
+        // Direct plugins are only known here after a restart. On startup,
+        // Pumpkin adds them through the loader
+        for plugin in direct_plugins {
+            let name = match lock.runtime.add_direct_plugin(&plugin.path) {
+                Ok(name) => name,
+                Err(e) => {
+                    log::error!("Failed to load plugin {:?}: {}", plugin.path, e);
+                    continue;
+                }
+            };
+
+            if plugin.enabled {
+                match lock.runtime.enable_plugin(&name).await {
+                    Ok(_) => lock.register_plugin_ref(&name),
+                    Err(e) => log::error!("Failed to enable plugin {}: {}", name, e),
+                }
+            }
+        }
     }
 
-    while let Ok(cmd) = rx.recv().await {
+    let mut control = control.lock().await;
+
+    loop {
+        // Management commands are handled before queued events
+        let command = tokio::select! {
+            biased;
+            command = control.recv() => command,
+            command = recv_command(&mut rx, "worker") => command,
+        };
+
+        let Some(cmd) = command else {
+            return Ok(());
+        };
+
         match cmd {
             LuaCommand::Reload { response } => {
-                let result = reload_lua(&manager);
+                let result = reload_lua(&manager).await;
                 let _ = response.send(result);
             }
             LuaCommand::GetPluginList { response } => {
-                let result = get_plugin_list(&manager);
+                let result = get_plugin_list(&manager).await;
                 let _ = response.send(result);
             }
             LuaCommand::EnablePlugin { name, response } => {
-                let result = enable_plugin(&manager, name);
+                let result = enable_plugin(&manager, name).await;
                 let _ = response.send(result);
             }
             LuaCommand::DisablePlugin { name, response } => {
-                let result = disable_plugin(&manager, name);
+                let result = disable_plugin(&manager, name).await;
                 let _ = response.send(result);
             }
             LuaCommand::ReloadPlugin { name, response } => {
-                let result = reload_plugin(&manager, &name);
+                let result = reload_plugin(&manager, &name).await;
                 let _ = response.send(result);
             }
             LuaCommand::GetPluginInfo { name, response } => {
-                let result = get_plugin_info(&manager, &name);
+                let result = get_plugin_info(&manager, &name).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::Eval {
+                plugin,
+                code,
+                response,
+            } => {
+                let result = eval_in_plugin(&manager, &plugin, &code).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::AddDirectPlugin { path, response } => {
+                let result = add_direct_plugin(&manager, &path).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::EnableDirectPlugin { name, response } => {
+                let result = enable_plugin(&manager, name).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::DisableDirectPlugin { name, response } => {
+                let result = disable_plugin(&manager, name).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::RemoveDirectPlugin { name, response } => {
+                let result = remove_direct_plugin(&manager, &name).await;
+                let _ = response.send(result);
+            }
+            LuaCommand::RunCommand {
+                owner,
+                callback,
+                sender,
+                arguments,
+                response,
+            } => {
+                let lua = manager.lock().await.runtime.lua.clone();
+                let result =
+                    commands::run_command(&lua, owner.as_deref(), callback, sender, arguments)
+                        .await;
+                let _ = response.send(result);
+            }
+            LuaCommand::SuggestCommand {
+                owner,
+                callback,
+                sender,
+                partial,
+                response,
+            } => {
+                let lua = manager.lock().await.runtime.lua.clone();
+                let result =
+                    commands::suggest_command(&lua, owner.as_deref(), callback, sender, partial)
+                        .await;
                 let _ = response.send(result);
             }
+            LuaCommand::Shutdown { response } => {
+                if let Err(e) = manager.lock().await.runtime.disable_all_plugins().await {
+                    log::error!("Failed to disable plugins on shutdown: {}", e);
+                }
+                let _ = response.send(());
+                return Ok(());
+            }
+            LuaCommand::SuspendPlugin { name } => {
+                suspend_plugin(&manager, &name).await;
+            }
             LuaCommand::TriggerEvent {
                 event_type,
+                tiers,
                 event_data,
+                response,
             } => {
-                handle_event(&manager, &event_type, &event_data);
+                let outcome = handle_event(&manager, &event_type, tiers, &event_data).await;
+                if let Some(response) = response {
+                    let _ = response.send(outcome);
+                }
             }
         }
     }
 }
 
-fn reload_lua(manager: &Mutex<LuaManager>) -> Result<()> {
+async fn reload_lua(manager: &Mutex<LuaManager>) -> Result<()> {
     let is_initialized = {
-        let lock = manager.lock().unwrap();
+        let lock = manager.lock().await;
         lock.initialized
     };
 
@@ -170,15 +337,16 @@ fn reload_lua(manager: &Mutex<LuaManager>) -> Result<()> {
         return Err(anyhow!("Cannot reload: Lua runtime not initialized"));
     }
 
-    let disable_result = {
-        let mut lock = manager.lock().unwrap();
+    let (disable_result, direct_plugins) = {
+        let mut lock = manager.lock().await;
 
         let plugins = lock.get_registered_plugins();
         for plugin in plugins {
             lock.clear_plugin_ref(&plugin);
         }
 
-        lock.runtime.disable_all_plugins()
+        let direct_plugins = lock.runtime.enabled_direct_plugins();
+        (lock.runtime.disable_all_plugins().await, direct_plugins)
     };
 
     if let Err(e) = disable_result {
@@ -186,7 +354,7 @@ fn reload_lua(manager: &Mutex<LuaManager>) -> Result<()> {
     }
 
     let discovery_result = {
-        let mut lock = manager.lock().unwrap();
+        let mut lock = manager.lock().await;
         lock.runtime.discover_plugins()
     };
 
@@ -195,105 +363,149 @@ fn reload_lua(manager: &Mutex<LuaManager>) -> Result<()> {
     }
 
     let config_manager_clone = {
-        let lock = manager.lock().unwrap();
+        let lock = manager.lock().await;
         lock.config_manager.clone()
     };
 
     let result = {
-        let mut lock = manager.lock().unwrap();
-        lock.runtime.load_enabled_plugins(&config_manager_clone)
+        let mut lock = manager.lock().await;
+        lock.runtime
+            .load_enabled_plugins(&config_manager_clone)
+            .await
     };
 
     if let Err(e) = result {
         return Err(anyhow!("Failed to reload enabled plugins: {}", e));
     }
+
+    let mut lock = manager.lock().await;
+    for name in direct_plugins {
+        match lock.runtime.enable_plugin(&name).await {
+            Ok(_) => lock.register_plugin_ref(&name),
+            Err(e) => log::error!("Failed to enable plugin {}: {}", name, e),
+        }
+    }
     Ok(())
 }
 
-fn get_plugin_list(manager: &Mutex<LuaManager>) -> Vec<(String, bool)> {
-    match manager.lock() {
-        Ok(lock) => lock
-            .runtime
-            .plugins
-            .iter()
-            .map(|(name, plugin)| (name.clone(), plugin.enabled))
-            .collect(),
-        Err(_) => Vec::new(),
-    }
+async fn get_plugin_list(manager: &Mutex<LuaManager>) -> Vec<(String, bool)> {
+    manager
+        .lock()
+        .await
+        .runtime
+        .plugins
+        .iter()
+        .map(|(name, plugin)| (name.clone(), plugin.enabled))
+        .collect()
 }
 
-fn reload_plugin(manager: &Mutex<LuaManager>, name: &str) -> Result<bool> {
-    let is_initialized = {
-        match manager.lock() {
-            Ok(lock) => lock.initialized,
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
-        }
-    };
+async fn reload_plugin(manager: &Mutex<LuaManager>, name: &str) -> Result<bool> {
+    let is_initialized = manager.lock().await.initialized;
 
     if !is_initialized {
         return Err(anyhow!("Cannot reload plugin: Lua runtime not initialized"));
     }
 
-    match manager.lock() {
-        Ok(mut lock) => {
-            lock.clear_plugin_ref(name);
+    let mut lock = manager.lock().await;
+    lock.clear_plugin_ref(name);
 
-            let result = lock.runtime.reload_plugin(name);
-
-            if result.is_ok() {
-                lock.register_plugin_ref(name);
-            } else {
-                println!("Failed to reload plugin {}: {:?}", name, result);
-            }
+    let result = lock.runtime.reload_plugin(name).await;
 
-            result
-        }
-        Err(_) => Err(anyhow!("Failed to acquire lock")),
+    if result.is_ok() {
+        lock.register_plugin_ref(name);
+    } else {
+        println!("Failed to reload plugin {}: {:?}", name, result);
     }
+
+    result
 }
 
-fn get_plugin_info(manager: &Mutex<LuaManager>, name: &str) -> Option<PluginInfo> {
-    let is_initialized = match manager.lock() {
-        Ok(lock) => lock.initialized,
-        Err(_) => return None,
-    };
+async fn get_plugin_info(manager: &Mutex<LuaManager>, name: &str) -> Option<PluginInfo> {
+    let is_initialized = manager.lock().await.initialized;
 
     if !is_initialized {
         return None;
     }
 
-    match manager.lock() {
-        Ok(lock) => lock.runtime.plugins.get(name).map(|plugin| {
-            (
-                plugin.manifest.name.clone(),
-                plugin.manifest.description.clone(),
-                plugin.manifest.version.clone(),
-                plugin.manifest.author.clone(),
-                plugin.enabled,
-                plugin.file_path.clone(),
-            )
-        }),
-        Err(_) => None,
+    let lock = manager.lock().await;
+    lock.runtime
+        .plugins
+        .get(name)
+        .map(|plugin| plugin_info(&lock.runtime, plugin))
+}
+
+fn plugin_info(runtime: &LuaRuntime, plugin: &LuaPlugin) -> PluginInfo {
+    PluginInfo {
+        name: plugin.manifest.name.clone(),
+        description: plugin.manifest.description.clone(),
+        version: plugin.manifest.version.clone(),
+        author: plugin.manifest.author.clone(),
+        enabled: plugin.enabled,
+        file_path: plugin.file_path.clone(),
+        budget_overruns: budget::overruns(&runtime.lua, &plugin.manifest.name),
+        memory_used: memory::usage(&runtime.lua, &plugin.manifest.name),
+        memory_limit: memory::limit(&runtime.lua, &plugin.manifest.name),
     }
 }
 
-fn enable_plugin(manager: &Mutex<LuaManager>, name: String) -> Result<bool> {
-    let is_initialized = {
-        match manager.lock() {
-            Ok(lock) => lock.initialized,
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
+/// Whether a plugin was loaded by Pumpkin, so it is not in `config.json`.
+fn is_direct(manager: &LuaManager, name: &str) -> bool {
+    manager
+        .runtime
+        .plugins
+        .get(name)
+        .is_some_and(|plugin| plugin.source == PluginSource::Direct)
+}
+
+async fn add_direct_plugin(manager: &Mutex<LuaManager>, path: &Path) -> Result<PluginInfo> {
+    let mut lock = manager.lock().await;
+    let name = lock.runtime.add_direct_plugin(path)?;
+
+    Ok(plugin_info(&lock.runtime, &lock.runtime.plugins[&name]))
+}
+
+async fn remove_direct_plugin(manager: &Mutex<LuaManager>, name: &str) -> Result<()> {
+    let mut lock = manager.lock().await;
+    lock.clear_plugin_ref(name);
+    lock.runtime.remove_direct_plugin(name).await
+}
+
+async fn eval_in_plugin(manager: &Mutex<LuaManager>, plugin: &str, code: &str) -> Result<String> {
+    let lua = {
+        let lock = manager.lock().await;
+
+        match lock.runtime.plugins.get(plugin) {
+            Some(loaded) if loaded.enabled => lock.runtime.lua.clone(),
+            Some(_) => return Err(anyhow!("Plugin '{}' is not enabled", plugin)),
+            None => return Err(anyhow!("Plugin '{}' not found", plugin)),
         }
     };
 
+    match tokio::time::timeout(EVAL_TIMEOUT, eval::eval(&lua, plugin, code)).await {
+        Ok(result) => result.map_err(|e| anyhow!("{}", e)),
+        Err(_) => Err(anyhow!(
+            "Evaluation timed out after {} seconds",
+            EVAL_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+async fn enable_plugin(manager: &Mutex<LuaManager>, name: String) -> Result<bool> {
+    let is_initialized = manager.lock().await.initialized;
+
     if !is_initialized {
         return Err(anyhow!("Cannot enable plugin: Lua runtime not initialized"));
     }
 
     let config_result = {
-        match manager.lock() {
-            Ok(mut lock) => lock.config_manager.enable_plugin(name.clone()),
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
+        let mut lock = manager.lock().await;
+        if is_direct(&lock, &name) {
+            let enabled = lock.runtime.enable_plugin(&name).await?;
+            lock.register_plugin_ref(&name);
+            return Ok(enabled);
         }
+
+        lock.config_manager.enable_plugin(name.clone())
     };
 
     let added_to_config = match config_result {
@@ -302,150 +514,120 @@ fn enable_plugin(manager: &Mutex<LuaManager>, name: String) -> Result<bool> {
     };
 
     let runtime_result = {
-        match manager.lock() {
-            Ok(mut lock) => {
-                lock.register_plugin_ref(&name);
+        let mut lock = manager.lock().await;
+        lock.register_plugin_ref(&name);
 
-                let result = lock.runtime.enable_plugin(&name);
-
-                if result.is_err() {
-                    println!("Failed to enable plugin {}: {:?}", &name, result);
-                }
+        let result = lock.runtime.enable_plugin(&name).await;
 
-                result
-            }
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
+        if result.is_err() {
+            println!("Failed to enable plugin {}: {:?}", &name, result);
         }
+
+        result
     };
 
-    if let Err(e) = runtime_result {
-        if let Ok(lock) = manager.lock() {
-            lock.clear_plugin_ref(&name);
+    match runtime_result {
+        // A plugin disabled for budget overruns is still in the config
+        Ok(enabled) => Ok(added_to_config || enabled),
+        Err(e) => {
+            manager.lock().await.clear_plugin_ref(&name);
+            Err(anyhow!("Failed to enable plugin in runtime: {}", e))
         }
-        return Err(anyhow!("Failed to enable plugin in runtime: {}", e));
     }
-
-    Ok(added_to_config)
 }
 
-static INIT_EVENT_SENDER: Once = Once::new();
-pub static mut EVENT_SENDER: Option<Sender<LuaCommand>> = None;
-
-pub fn send_event_command(command: LuaCommand) -> Result<()> {
-    unsafe {
-        #[allow(static_mut_refs)]
-        if let Some(sender) = &EVENT_SENDER {
-            sender
-                .send(command)
-                .map_err(|_| anyhow!("Failed to send command to Lua worker"))?;
-            Ok(())
-        } else {
-            Err(anyhow!("Event sender not initialized"))
-        }
+/// Disables a plugin in the runtime only, leaving `config.json` untouched.
+async fn suspend_plugin(manager: &Mutex<LuaManager>, name: &str) {
+    let mut lock = manager.lock().await;
+    lock.clear_plugin_ref(name);
+
+    match lock.runtime.disable_plugin(name).await {
+        Ok(true) => log::warn!("Plugin {} was disabled", name),
+        Ok(false) => {}
+        Err(e) => log::error!("Failed to disable plugin {}: {}", name, e),
     }
 }
 
-fn init_event_sender(tx: Sender<LuaCommand>) {
-    INIT_EVENT_SENDER.call_once(|| unsafe {
-        EVENT_SENDER = Some(tx);
-    });
+/// Sends an event to the Lua worker, returning how many receivers got it.
+pub fn send_event_command(command: LuaCommand) -> Result<usize> {
+    super::manager()?.send_event(command)
 }
 
-fn handle_event(manager: &Mutex<LuaManager>, event_type: &str, event_data: &str) {
-    match manager.lock() {
-        Ok(lock) => {
-            if !lock.initialized {
-                return;
-            }
+/// How many events are queued for the Lua worker.
+pub fn command_backlog() -> usize {
+    super::manager().map_or(0, |manager| manager.backlog())
+}
 
-            match event_type {
-                "player_join" => {
-                    if let Err(e) =
-                        events::player_join::trigger_event(&lock.runtime.lua, event_data)
-                    {
-                        log::error!("Error triggering player_join event: {}", e);
-                    }
-                }
-                "player_leave" => {
-                    if let Err(e) =
-                        events::player_leave::trigger_event(&lock.runtime.lua, event_data)
-                    {
-                        log::error!("Error triggering player_leave event: {}", e);
-                    }
-                }
-                "player_chat" => {
-                    if let Err(e) =
-                        events::player_chat::trigger_event(&lock.runtime.lua, event_data)
-                    {
-                        log::error!("Error triggering player_chat event: {}", e);
-                    }
-                }
-                "block_place" => {
-                    if let Err(e) =
-                        events::block_place::trigger_event(&lock.runtime.lua, event_data)
-                    {
-                        log::error!("Error triggering block_place event: {}", e);
-                    }
-                }
-                "block_break" => {
-                    if let Err(e) =
-                        events::block_break::trigger_event(&lock.runtime.lua, event_data)
-                    {
-                        log::error!("Error triggering block_break event: {}", e);
-                    }
-                }
-                _ => {
-                    log::warn!("Unknown event type: {}", event_type);
-                }
+/// Receives the next event for a Lua runtime. When the runtime fell behind
+/// and missed events, they are counted as dropped and receiving carries on
+/// with the oldest event still queued. Returns `None` once the channel closes.
+pub async fn recv_command(rx: &mut Receiver<LuaCommand>, runtime: &str) -> Option<LuaCommand> {
+    loop {
+        match rx.recv().await {
+            Ok(command) => return Some(command),
+            Err(RecvError::Lagged(missed)) => {
+                delivery::record_dropped(missed);
+                log::warn!(
+                    "Lua runtime {} fell behind and dropped {} events",
+                    runtime,
+                    missed
+                );
             }
-        }
-        Err(e) => {
-            log::error!("Failed to acquire lock for event handling: {:?}", e);
+            Err(RecvError::Closed) => return None,
         }
     }
 }
 
-fn disable_plugin(manager: &Mutex<LuaManager>, name: String) -> Result<bool> {
-    let is_initialized = {
-        match manager.lock() {
-            Ok(lock) => lock.initialized,
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
+async fn handle_event(
+    manager: &Mutex<LuaManager>,
+    event_type: &str,
+    tiers: &[ListenerPriority],
+    event_data: &EventPayload,
+) -> EventOutcome {
+    let lua = {
+        let lock = manager.lock().await;
+        if !lock.initialized {
+            return EventOutcome::default();
         }
+
+        lock.runtime.lua.clone()
     };
 
+    events::trigger_event(&lua, event_type, tiers, event_data).await
+}
+
+async fn disable_plugin(manager: &Mutex<LuaManager>, name: String) -> Result<bool> {
+    let is_initialized = manager.lock().await.initialized;
+
     if !is_initialized {
         return Err(anyhow!(
             "Cannot disable plugin: Lua runtime not initialized"
         ));
     }
 
-    let runtime_result = {
-        match manager.lock() {
-            Ok(mut lock) => {
-                lock.clear_plugin_ref(&name);
-
-                let result = lock.runtime.disable_plugin(&name);
+    let (runtime_result, direct) = {
+        let mut lock = manager.lock().await;
+        lock.clear_plugin_ref(&name);
 
-                if result.is_err() {
-                    println!("Failed to disable plugin {}: {:?}", &name, result);
-                }
+        let result = lock.runtime.disable_plugin(&name).await;
 
-                result
-            }
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
+        if result.is_err() {
+            println!("Failed to disable plugin {}: {:?}", &name, result);
         }
+
+        (result, is_direct(&lock, &name))
+    };
+
+    let disabled = match runtime_result {
+        Ok(disabled) => disabled,
+        Err(e) => return Err(anyhow!("Failed to disable plugin in runtime: {}", e)),
     };
 
-    if let Err(e) = runtime_result {
-        return Err(anyhow!("Failed to disable plugin in runtime: {}", e));
+    if direct {
+        return Ok(disabled);
     }
 
-    let config_result = {
-        match manager.lock() {
-            Ok(mut lock) => lock.config_manager.disable_plugin(&name),
-            Err(_) => return Err(anyhow!("Failed to acquire lock")),
-        }
-    };
+    let config_result = manager.lock().await.config_manager.disable_plugin(&name);
 
     match config_result {
         Ok(removed) => Ok(removed),